@@ -0,0 +1,144 @@
+use relm4::gtk::{
+    gio::{self, prelude::*, DBusCallFlags, DBusConnection},
+    glib,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::sysfs::read_to_string_buf;
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+// https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+const LOGIND_NAME: &str = "org.freedesktop.login1";
+const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Brightness {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Brightness {
+    pub fn percentage(&self) -> u32 {
+        if self.max == 0 {
+            return 0;
+        }
+
+        (u64::from(self.current) * 100 / u64::from(self.max)) as u32
+    }
+
+    /// Brightness after changing it by `step` percent of the maximum, never going below `min`
+    /// percent so that the screen doesn't turn off
+    pub fn step(&self, step: i32, min: u32) -> u32 {
+        let max = i64::from(self.max);
+        let delta = max * i64::from(step) / 100;
+        // Steps smaller than one unit would never change anything
+        let delta = if delta == 0 {
+            i64::from(step.signum())
+        } else {
+            delta
+        };
+        let min = (max * i64::from(min) / 100).max(1);
+
+        (i64::from(self.current) + delta).clamp(min.min(max), max) as u32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Backlight {
+    /// Device name, e.g. `intel_backlight`
+    pub name: String,
+    path: PathBuf,
+}
+
+impl Backlight {
+    /// Finds the backlight with the given name, or the first one
+    pub fn find(name: Option<&str>) -> io::Result<Option<Self>> {
+        let mut names = Path::new(BACKLIGHT_DIR)
+            .read_dir()?
+            .filter_map(|entry| Some(entry.ok()?.file_name().to_string_lossy().to_string()))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let name = match name {
+            Some(name) => names.into_iter().find(|other| other == name),
+            None => names.into_iter().next(),
+        };
+
+        Ok(name.map(|name| Self {
+            path: Path::new(BACKLIGHT_DIR).join(&name),
+            name,
+        }))
+    }
+
+    pub fn read(&self) -> io::Result<Brightness> {
+        let mut buffer = String::with_capacity(16);
+        let mut read = |file: &str| {
+            read_to_string_buf(self.path.join(file), &mut buffer)?
+                .trim_end()
+                .parse::<u32>()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        };
+
+        // `brightness` is only the last requested value, `actual_brightness` also follows changes
+        // made by the firmware, e.g. through hotkeys
+        Ok(Brightness {
+            current: read("actual_brightness")?,
+            max: read("max_brightness")?,
+        })
+    }
+
+    /// Sets the brightness through logind, which doesn't require write access to sysfs
+    pub async fn set(&self, connection: &DBusConnection, value: u32) -> Result<(), glib::Error> {
+        connection
+            .call_future(
+                Some(LOGIND_NAME),
+                SESSION_PATH,
+                SESSION_INTERFACE,
+                "SetBrightness",
+                Some(&("backlight", self.name.as_str(), value).to_variant()),
+                None,
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Watches the brightness with inotify instead of polling it. The kernel notifies every change
+    /// on `actual_brightness`, including the ones written to `brightness`
+    pub fn monitor(&self) -> Result<gio::FileMonitor, glib::Error> {
+        gio::File::for_path(self.path.join("actual_brightness"))
+            .monitor_file(gio::FileMonitorFlags::NONE, None::<&gio::Cancellable>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step() {
+        let brightness = Brightness {
+            current: 500,
+            max: 1000,
+        };
+        assert_eq!(brightness.step(5, 1), 550);
+        assert_eq!(brightness.step(-5, 1), 450);
+        assert_eq!(brightness.step(100, 1), 1000);
+        assert_eq!(brightness.step(-100, 10), 100);
+    }
+
+    #[test]
+    fn step_small_max() {
+        let brightness = Brightness { current: 3, max: 7 };
+        assert_eq!(brightness.step(5, 0), 4);
+        assert_eq!(brightness.step(-5, 0), 2);
+        assert_eq!(Brightness { current: 1, max: 7 }.step(-5, 0), 1);
+    }
+}
@@ -1,22 +1,13 @@
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{self, Read},
-    path::Path,
-};
+use std::{collections::HashMap, path::Path, time::Duration};
 
+use crate::sysfs::read_to_string_buf;
+
+#[derive(Debug, Clone)]
 pub struct BatteryInfo {
     pub capacity: i32,
     pub status: String,
-}
-
-fn read_to_string_buf<P: AsRef<Path>>(path: P, buffer: &mut String) -> io::Result<&mut String> {
-    let mut file = File::open(path)?;
-
-    buffer.clear();
-    file.read_to_string(buffer)?;
-
-    Ok(buffer)
+    /// Time until the battery is empty or full, only estimated by UPower
+    pub estimate: Option<Duration>,
 }
 
 // https://github.com/elkowar/eww/blob/dc3129aee2806823bdad87785f7ef80651d5245c/crates/eww/src/config/system_stats.rs#L118
@@ -61,7 +52,11 @@ pub fn get_batteries() -> HashMap<String, BatteryInfo> {
                 .expect("Failed to get battery name")
                 .to_string_lossy()
                 .to_string(),
-            BatteryInfo { capacity, status },
+            BatteryInfo {
+                capacity,
+                status,
+                estimate: None,
+            },
         );
     }
 
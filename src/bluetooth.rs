@@ -0,0 +1,389 @@
+use relm4::gtk::{
+    gio::{self, prelude::*, BusNameWatcherFlags, DBusCallFlags, DBusConnection, DBusSignalFlags},
+    glib::{self, Variant, VariantDict},
+};
+use std::{collections::BTreeMap, rc::Rc};
+
+// https://github.com/bluez/bluez/tree/master/doc
+const BLUEZ_NAME: &str = "org.bluez";
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+const OBJECT_MANAGER_INTERFACE: &str = "org.freedesktop.DBus.ObjectManager";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Adapter {
+    pub alias: String,
+    pub powered: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Device {
+    pub alias: String,
+    /// Freedesktop icon name, e.g. `audio-headset`
+    pub icon: String,
+    pub paired: bool,
+    pub connected: bool,
+    /// Only set for devices reporting it through `org.bluez.Battery1`
+    pub battery: Option<u8>,
+}
+
+#[derive(Debug)]
+pub enum BluezEvent {
+    InterfacesAdded {
+        path: String,
+        /// `a{sa{sv}}` of interfaces and their properties
+        interfaces: Variant,
+    },
+    InterfacesRemoved {
+        path: String,
+        interfaces: Vec<String>,
+    },
+    PropertiesChanged {
+        path: String,
+        interface: String,
+        changed: Variant,
+    },
+}
+
+/// Adapters and devices by object path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BluetoothState {
+    pub adapters: BTreeMap<String, Adapter>,
+    pub devices: BTreeMap<String, Device>,
+}
+
+impl BluetoothState {
+    /// Builds the state from the `a{oa{sa{sv}}}` returned by `GetManagedObjects`
+    fn from_managed_objects(objects: &Variant) -> Self {
+        let mut state = Self::default();
+        for object in objects.iter() {
+            if let Some(path) = object.child_value(0).str() {
+                state.add_interfaces(path, &object.child_value(1));
+            }
+        }
+
+        state
+    }
+
+    fn add_interfaces(&mut self, path: &str, interfaces: &Variant) {
+        for interface in interfaces.iter() {
+            if let Some(name) = interface.child_value(0).str() {
+                self.update_properties(path, name, &interface.child_value(1));
+            }
+        }
+    }
+
+    fn update_properties(&mut self, path: &str, interface: &str, properties: &Variant) {
+        let properties = VariantDict::new(Some(properties));
+        let string = |key| properties.lookup::<String>(key).ok().flatten();
+        let flag = |key| properties.lookup::<bool>(key).ok().flatten();
+
+        match interface {
+            ADAPTER_INTERFACE => {
+                let adapter = self.adapters.entry(path.to_string()).or_default();
+                if let Some(alias) = string("Alias") {
+                    adapter.alias = alias;
+                }
+                if let Some(powered) = flag("Powered") {
+                    adapter.powered = powered;
+                }
+            }
+            DEVICE_INTERFACE => {
+                let device = self.devices.entry(path.to_string()).or_default();
+                if let Some(alias) = string("Alias") {
+                    device.alias = alias;
+                }
+                if let Some(icon) = string("Icon") {
+                    device.icon = icon;
+                }
+                if let Some(paired) = flag("Paired") {
+                    device.paired = paired;
+                }
+                if let Some(connected) = flag("Connected") {
+                    device.connected = connected;
+                }
+            }
+            BATTERY_INTERFACE => {
+                if let Some(percentage) = properties.lookup::<u8>("Percentage").ok().flatten() {
+                    self.devices.entry(path.to_string()).or_default().battery = Some(percentage);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn apply(&mut self, event: BluezEvent) {
+        match event {
+            BluezEvent::InterfacesAdded { path, interfaces } => {
+                self.add_interfaces(&path, &interfaces);
+            }
+            BluezEvent::InterfacesRemoved { path, interfaces } => {
+                for interface in interfaces {
+                    match interface.as_str() {
+                        ADAPTER_INTERFACE => {
+                            self.adapters.remove(&path);
+                        }
+                        DEVICE_INTERFACE => {
+                            self.devices.remove(&path);
+                        }
+                        BATTERY_INTERFACE => {
+                            if let Some(device) = self.devices.get_mut(&path) {
+                                device.battery = None;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            BluezEvent::PropertiesChanged {
+                path,
+                interface,
+                changed,
+            } => self.update_properties(&path, &interface, &changed),
+        }
+    }
+
+    pub fn powered(&self) -> bool {
+        self.adapters.values().any(|adapter| adapter.powered)
+    }
+
+    pub fn connected_devices(&self) -> impl Iterator<Item = &Device> {
+        self.devices.values().filter(|device| device.connected)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bluez {
+    connection: DBusConnection,
+}
+
+impl Bluez {
+    pub fn new(connection: DBusConnection) -> Self {
+        Self { connection }
+    }
+
+    pub async fn state(&self) -> Result<BluetoothState, glib::Error> {
+        let objects = self
+            .connection
+            .call_future(
+                Some(BLUEZ_NAME),
+                "/",
+                OBJECT_MANAGER_INTERFACE,
+                "GetManagedObjects",
+                None,
+                None,
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await?;
+
+        Ok(BluetoothState::from_managed_objects(
+            &objects.child_value(0),
+        ))
+    }
+
+    /// Calls `f` with every change of the adapters and devices
+    pub fn watch<F: Fn(BluezEvent) + 'static>(&self, f: F) {
+        let f = Rc::new(f);
+
+        self.connection.signal_subscribe(
+            Some(BLUEZ_NAME),
+            Some(OBJECT_MANAGER_INTERFACE),
+            None,
+            Some("/"),
+            None,
+            DBusSignalFlags::NONE,
+            {
+                let f = f.clone();
+                move |_, _, _, _, signal, parameters| {
+                    let Some(path) = parameters.child_value(0).str().map(String::from) else {
+                        return;
+                    };
+
+                    match signal {
+                        "InterfacesAdded" => f(BluezEvent::InterfacesAdded {
+                            path,
+                            interfaces: parameters.child_value(1),
+                        }),
+                        "InterfacesRemoved" => f(BluezEvent::InterfacesRemoved {
+                            path,
+                            interfaces: parameters.child_value(1).get().unwrap_or_default(),
+                        }),
+                        _ => {}
+                    }
+                }
+            },
+        );
+
+        self.connection.signal_subscribe(
+            Some(BLUEZ_NAME),
+            Some(PROPERTIES_INTERFACE),
+            Some("PropertiesChanged"),
+            None,
+            // Only the bluez interfaces
+            Some(BLUEZ_NAME),
+            DBusSignalFlags::MATCH_ARG0_NAMESPACE,
+            move |_, _, path, _, _, parameters| {
+                let Some(interface) = parameters.child_value(0).str().map(String::from) else {
+                    return;
+                };
+
+                f(BluezEvent::PropertiesChanged {
+                    path: path.to_string(),
+                    interface,
+                    changed: parameters.child_value(1),
+                });
+            },
+        );
+    }
+
+    /// Calls `f` with whether bluetoothd is running, once it's known and on every change
+    pub fn watch_service<F: Fn(bool) + 'static>(&self, f: F) {
+        let f = Rc::new(f);
+        gio::bus_watch_name_on_connection(
+            &self.connection,
+            BLUEZ_NAME,
+            BusNameWatcherFlags::NONE,
+            {
+                let f = f.clone();
+                move |_, _, _| f(true)
+            },
+            move |_, _| f(false),
+        );
+    }
+
+    pub async fn set_powered(&self, adapter: &str, powered: bool) -> Result<(), glib::Error> {
+        self.connection
+            .call_future(
+                Some(BLUEZ_NAME),
+                adapter,
+                PROPERTIES_INTERFACE,
+                "Set",
+                Some(&(ADAPTER_INTERFACE, "Powered", powered.to_variant()).to_variant()),
+                None,
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Calls `Connect` or `Disconnect` on the device
+    pub async fn call_device(&self, device: &str, method: &str) -> Result<(), glib::Error> {
+        self.connection
+            .call_future(
+                Some(BLUEZ_NAME),
+                device,
+                DEVICE_INTERFACE,
+                method,
+                None,
+                None,
+                DBusCallFlags::NONE,
+                // Connecting can take a while
+                30_000,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties(entries: &[(&str, Variant)]) -> Variant {
+        let dict = VariantDict::new(None);
+        for (key, value) in entries {
+            dict.insert_value(key, value);
+        }
+        dict.end()
+    }
+
+    fn interfaces(entries: &[(&str, Variant)]) -> Variant {
+        Variant::array_from_iter_with_type(
+            glib::VariantTy::new("{sa{sv}}").unwrap(),
+            entries.iter().map(|(interface, properties)| {
+                Variant::from_dict_entry(&interface.to_variant(), properties)
+            }),
+        )
+    }
+
+    #[test]
+    fn device_with_battery() {
+        let mut state = BluetoothState::default();
+        state.apply(BluezEvent::InterfacesAdded {
+            path: "/org/bluez/hci0/dev_00".to_string(),
+            interfaces: interfaces(&[
+                (
+                    DEVICE_INTERFACE,
+                    properties(&[
+                        ("Alias", "Headphones".to_variant()),
+                        ("Paired", true.to_variant()),
+                        ("Connected", false.to_variant()),
+                    ]),
+                ),
+                (
+                    BATTERY_INTERFACE,
+                    properties(&[("Percentage", 80u8.to_variant())]),
+                ),
+            ]),
+        });
+
+        state.apply(BluezEvent::PropertiesChanged {
+            path: "/org/bluez/hci0/dev_00".to_string(),
+            interface: DEVICE_INTERFACE.to_string(),
+            changed: properties(&[("Connected", true.to_variant())]),
+        });
+
+        assert_eq!(
+            state.connected_devices().collect::<Vec<_>>(),
+            vec![&Device {
+                alias: "Headphones".to_string(),
+                icon: String::new(),
+                paired: true,
+                connected: true,
+                battery: Some(80),
+            }]
+        );
+
+        state.apply(BluezEvent::InterfacesRemoved {
+            path: "/org/bluez/hci0/dev_00".to_string(),
+            interfaces: vec![BATTERY_INTERFACE.to_string()],
+        });
+        assert_eq!(state.devices["/org/bluez/hci0/dev_00"].battery, None);
+
+        state.apply(BluezEvent::InterfacesRemoved {
+            path: "/org/bluez/hci0/dev_00".to_string(),
+            interfaces: vec![DEVICE_INTERFACE.to_string()],
+        });
+        assert!(state.devices.is_empty());
+    }
+
+    #[test]
+    fn adapter_power() {
+        let mut state = BluetoothState::default();
+        state.apply(BluezEvent::InterfacesAdded {
+            path: "/org/bluez/hci0".to_string(),
+            interfaces: interfaces(&[(
+                ADAPTER_INTERFACE,
+                properties(&[
+                    ("Alias", "laptop".to_variant()),
+                    ("Powered", false.to_variant()),
+                ]),
+            )]),
+        });
+        assert!(!state.powered());
+
+        state.apply(BluezEvent::PropertiesChanged {
+            path: "/org/bluez/hci0".to_string(),
+            interface: ADAPTER_INTERFACE.to_string(),
+            changed: properties(&[("Powered", true.to_variant())]),
+        });
+        assert!(state.powered());
+        assert_eq!(state.adapters["/org/bluez/hci0"].alias, "laptop");
+    }
+}
@@ -10,11 +10,253 @@ pub struct Margins {
     pub bottom: Option<i32>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SensorSelector {
+    pub chip: String,
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TemperatureConfig {
+    pub hwmon_path: Option<PathBuf>,
+    /// Selects the temperatures and fans to show, all of them if unset
+    pub sensors: Option<Vec<SensorSelector>>,
+    pub warning: Option<f32>,
+    pub critical: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryFormat {
+    #[default]
+    Percentage,
+    Absolute,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryUsage {
+    /// Memory that can't be reclaimed, i.e. total minus available
+    #[default]
+    Used,
+    /// Memory available for new allocations without swapping
+    Available,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct MemoryConfig {
+    pub format: Option<MemoryFormat>,
+    pub usage: Option<MemoryUsage>,
+    pub show_swap: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiskConfig {
+    pub mount_points: Option<Vec<PathBuf>>,
+    pub show_io: Option<bool>,
+    pub warning: Option<f32>,
+    pub critical: Option<f32>,
+}
+
+/// Popover of the time widget, opened with a right click, or a left click without `alt_format`
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct CalendarConfig {
+    pub show_week_numbers: Option<bool>,
+    pub ics_paths: Option<Vec<PathBuf>>,
+    pub upcoming_days: Option<u32>,
+    pub show_countdown: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TimeConfig {
+    pub format: Option<String>,
+    /// Toggled with a left click
+    pub alt_format: Option<String>,
+    pub tooltip_format: Option<String>,
+    pub timezones: Option<Vec<String>>,
+    pub calendar: Option<CalendarConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EllipsisPosition {
+    Start,
+    Middle,
+    #[default]
+    End,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveWindowDisplay {
+    #[default]
+    Title,
+    Class,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RewriteRule {
+    /// Window class the rule applies to, or all windows if not set
+    pub class: Option<String>,
+    pub pattern: String,
+    pub replacement: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ActiveWindowConfig {
+    pub icon_map: Option<HashMap<String, String>>,
+    pub max_length: Option<usize>,
+    pub ellipsis: Option<EllipsisPosition>,
+    pub rewrite_rules: Option<Vec<RewriteRule>>,
+    pub display: Option<ActiveWindowDisplay>,
+    pub hide_empty: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MprisConfig {
+    /// Maximum width of the track in characters
+    pub max_length: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrayConfig {
+    pub icon_size: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NotificationsConfig {
+    /// Milliseconds until popups expire when the notification doesn't specify it
+    pub default_timeout: Option<u32>,
+    pub max_history: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CustomConfig {
+    /// Added as a CSS class to tell custom widgets apart
+    pub name: Option<String>,
+    /// Command run with `sh -c`, printing either plain text or JSON with `text`, `tooltip`,
+    /// `class` and `percentage`
+    pub exec: String,
+    /// Seconds between runs of `exec`, at least 1. If not set, it's kept running and every line it
+    /// prints updates the widget.
+    pub interval: Option<u32>,
+    /// Icons shown before the text, picked by the percentage
+    pub icons: Option<Vec<String>>,
+    pub on_click: Option<String>,
+    pub on_middle_click: Option<String>,
+    pub on_right_click: Option<String>,
+    pub on_scroll_up: Option<String>,
+    pub on_scroll_down: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BacklightConfig {
+    /// Name of the device in `/sys/class/backlight`, the first one if not set
+    pub device: Option<String>,
+    /// Percent of the maximum brightness changed per scroll step
+    pub step: Option<u32>,
+    /// Percent of the maximum brightness scrolling doesn't go below
+    pub min_brightness: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryBackend {
+    /// Reads `/sys/class/power_supply` every second
+    #[default]
+    Sysfs,
+    /// Listens to `org.freedesktop.UPower`, which also knows about peripherals and estimates
+    Upower,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PeripheralBatteries {
+    #[default]
+    Hidden,
+    Tooltip,
+    /// Shown next to the battery
+    Items,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct BatteryConfig {
+    pub backend: Option<BatteryBackend>,
+    /// Batteries of mice, headsets etc., only supported by the UPower backend
+    pub peripherals: Option<PeripheralBatteries>,
+}
+
+/// Shows the BlueZ adapters and devices when set
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BluetoothConfig {}
+
+/// Shows and cycles the power-profiles-daemon profile when set
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PowerProfilesConfig {}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IdleInhibitorConfig {
+    /// Minutes until the inhibitor is disabled again, never if not set
+    pub timeout: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct NetworkConfig {
+    /// Shows the connection status from NetworkManager, with a popover to switch networks
+    pub network_manager: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PersistentWorkspace {
+    pub id: i64,
+    /// Shown instead of the workspace name
+    pub name: Option<String>,
+    /// Shown instead of the name
+    pub icon: Option<String>,
+    /// Only show the workspace on this monitor while it doesn't exist
+    pub monitor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WorkspacesConfig {
+    /// Show workspaces of other monitors too, they get the `other-monitor` class
+    pub show_all_monitors: Option<bool>,
+    /// Icons of the windows on each workspace by window class, shown after its name
+    pub window_icons: Option<HashMap<String, String>>,
+    /// Icon for window classes missing from `window_icons`
+    pub default_window_icon: Option<String>,
+    /// Workspaces that are shown even when they don't exist
+    pub persistent: Option<Vec<PersistentWorkspace>>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
+    /// Connector name of the monitor to put the bar on, e.g. `DP-1`. Start a bar with a
+    /// config for each monitor with `crabbar --config <path>` to get one on every monitor
+    pub monitor: Option<String>,
     pub margins: Option<Margins>,
     pub image_path: Option<PathBuf>,
     pub network_name: String,
+    pub network: Option<NetworkConfig>,
     pub battery_name: Option<String>,
+    pub battery: Option<BatteryConfig>,
     pub layout_map: Option<HashMap<String, String>>,
+    pub layout_keyboard: Option<String>,
+    pub submap_map: Option<HashMap<String, String>>,
+    pub temperature: Option<TemperatureConfig>,
+    pub memory: Option<MemoryConfig>,
+    pub processes_count: Option<usize>,
+    pub disk: Option<DiskConfig>,
+    pub time: Option<TimeConfig>,
+    pub active_window: Option<ActiveWindowConfig>,
+    pub workspaces: Option<WorkspacesConfig>,
+    pub mpris: Option<MprisConfig>,
+    pub tray: Option<TrayConfig>,
+    /// Runs a notification daemon when set
+    pub notifications: Option<NotificationsConfig>,
+    pub custom: Option<Vec<CustomConfig>>,
+    pub backlight: Option<BacklightConfig>,
+    pub bluetooth: Option<BluetoothConfig>,
+    pub power_profiles: Option<PowerProfilesConfig>,
+    pub idle_inhibitor: Option<IdleInhibitorConfig>,
 }
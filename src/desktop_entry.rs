@@ -0,0 +1,283 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+struct DesktopEntry {
+    /// Desktop file ID, without the `.desktop` suffix
+    id: String,
+    startup_wm_class: Option<String>,
+    icon: Option<String>,
+}
+
+// https://specifications.freedesktop.org/basedir-spec/latest/
+fn data_dirs() -> Vec<PathBuf> {
+    let data_home = env::var("XDG_DATA_HOME").map_or_else(
+        |_| {
+            let mut home = PathBuf::from(env::var("HOME").expect("HOME is not set"));
+            home.push(".local/share");
+            home
+        },
+        PathBuf::from,
+    );
+
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    std::iter::once(data_home)
+        .chain(env::split_paths(&data_dirs))
+        .collect()
+}
+
+fn parse_desktop_entry(id: String, data: &str) -> DesktopEntry {
+    let mut entry = DesktopEntry {
+        id,
+        startup_wm_class: None,
+        icon: None,
+    };
+
+    let mut in_desktop_entry = false;
+    for line in data.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_desktop_entry = line == "[Desktop Entry]";
+            continue;
+        }
+
+        if !in_desktop_entry {
+            continue;
+        }
+
+        // Localized keys like `Icon[de]` don't match here
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        match key.trim_end() {
+            "StartupWMClass" => entry.startup_wm_class = Some(value.trim_start().to_string()),
+            "Icon" => entry.icon = Some(value.trim_start().to_string()),
+            _ => {}
+        }
+    }
+
+    entry
+}
+
+fn collect_desktop_entries(
+    applications_dir: &Path,
+    dir: &Path,
+    entries: &mut Vec<DesktopEntry>,
+    seen: &mut HashSet<String>,
+) {
+    let Ok(dir_entries) = dir.read_dir() else {
+        return;
+    };
+
+    for dir_entry in dir_entries.flatten() {
+        let path = dir_entry.path();
+        if path.is_dir() {
+            collect_desktop_entries(applications_dir, &path, entries, seen);
+            continue;
+        }
+
+        if path.extension().is_none_or(|ext| ext != "desktop") {
+            continue;
+        }
+
+        // https://specifications.freedesktop.org/desktop-entry-spec/latest/file-naming.html#desktop-file-id
+        let Some(id) = path
+            .strip_prefix(applications_dir)
+            .ok()
+            .and_then(|relative| relative.with_extension("").to_str().map(str::to_owned))
+            .map(|id| id.replace('/', "-"))
+        else {
+            continue;
+        };
+
+        // Entries from earlier data dirs take precedence
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+
+        if let Ok(data) = fs::read_to_string(&path) {
+            entries.push(parse_desktop_entry(id, &data));
+        }
+    }
+}
+
+/// Resolves window classes to icon names or paths using desktop entries
+pub struct IconResolver {
+    overrides: HashMap<String, String>,
+    entries: Vec<DesktopEntry>,
+    cache: HashMap<String, String>,
+}
+
+impl IconResolver {
+    pub fn new(overrides: HashMap<String, String>) -> Self {
+        Self::with_data_dirs(overrides, data_dirs())
+    }
+
+    fn with_data_dirs(overrides: HashMap<String, String>, data_dirs: Vec<PathBuf>) -> Self {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+        for data_dir in data_dirs {
+            let applications_dir = data_dir.join("applications");
+            collect_desktop_entries(
+                &applications_dir,
+                &applications_dir,
+                &mut entries,
+                &mut seen,
+            );
+        }
+
+        Self {
+            overrides,
+            entries,
+            cache: HashMap::new(),
+        }
+    }
+
+    fn find_icon(&self, class: &str) -> Option<&str> {
+        let with_icon = || self.entries.iter().filter(|entry| entry.icon.is_some());
+
+        // Reverse-DNS IDs like `org.mozilla.firefox` are matched by their last component too
+        let short_class = class.rsplit('.').next().unwrap_or(class);
+
+        with_icon()
+            .find(|entry| {
+                entry
+                    .startup_wm_class
+                    .as_ref()
+                    .is_some_and(|wm_class| wm_class.eq_ignore_ascii_case(class))
+            })
+            .or_else(|| with_icon().find(|entry| entry.id.eq_ignore_ascii_case(class)))
+            .or_else(|| {
+                with_icon().find(|entry| {
+                    entry
+                        .id
+                        .rsplit('.')
+                        .next()
+                        .is_some_and(|id| id.eq_ignore_ascii_case(short_class))
+                })
+            })
+            .and_then(|entry| entry.icon.as_deref())
+    }
+
+    /// Returns an icon name or an absolute path to the icon file
+    pub fn resolve(&mut self, class: &str) -> String {
+        if let Some(icon) = self.overrides.get(class) {
+            return icon.clone();
+        }
+
+        if let Some(icon) = self.cache.get(class) {
+            return icon.clone();
+        }
+
+        let icon = if let Some(app_id) = class.strip_prefix("steam_app_") {
+            // Steam shortcuts don't set StartupWMClass, but install icons by app ID
+            format!("steam_icon_{app_id}")
+        } else {
+            // Fall back to the class itself, which matches the icon name for some apps
+            self.find_icon(class).unwrap_or(class).to_string()
+        };
+        self.cache.insert(class.to_string(), icon.clone());
+
+        icon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// Writes `applications/{file}` in the data dir
+    fn entry(dir: &TempDir, data_dir: &str, file: &str, data: &str) {
+        dir.write(Path::new(data_dir).join("applications").join(file), data);
+    }
+
+    fn resolver(dir: &TempDir, overrides: &[(&str, &str)]) -> IconResolver {
+        let overrides = overrides
+            .iter()
+            .map(|(class, icon)| (class.to_string(), icon.to_string()))
+            .collect();
+        let path = dir.path();
+        IconResolver::with_data_dirs(overrides, vec![path.join("home"), path.join("usr")])
+    }
+
+    #[test]
+    fn resolve() {
+        let dir = TempDir::new("desktop-entry-resolve");
+        entry(
+            &dir,
+            "usr",
+            "code.desktop",
+            "[Desktop Entry]\nName=Code\nIcon=vscode\nStartupWMClass=Code\n",
+        );
+        entry(
+            &dir,
+            "usr",
+            "org.mozilla.firefox.desktop",
+            "[Desktop Entry]\nIcon=firefox\n",
+        );
+        entry(
+            &dir,
+            "usr",
+            "kde/dolphin.desktop",
+            "[Desktop Entry]\nIcon=system-file-manager\n",
+        );
+        entry(
+            &dir,
+            "usr",
+            "localized.desktop",
+            "[Desktop Entry]\nIcon[de]=lokal\n\n[Desktop Action new]\nIcon=action\n",
+        );
+        // Overrides the entry with the same ID in the later data dir
+        entry(
+            &dir,
+            "home",
+            "Alacritty.desktop",
+            "[Desktop Entry]\nIcon=custom-terminal\n",
+        );
+        entry(
+            &dir,
+            "usr",
+            "Alacritty.desktop",
+            "[Desktop Entry]\nIcon=Alacritty\n",
+        );
+
+        let mut resolver = resolver(&dir, &[("kitty", "terminal")]);
+
+        let cases = [
+            // StartupWMClass, case-insensitively
+            ("Code", "vscode"),
+            ("code", "vscode"),
+            // File ID
+            ("org.mozilla.firefox", "firefox"),
+            ("ORG.MOZILLA.FIREFOX", "firefox"),
+            // Last component of reverse-DNS IDs
+            ("firefox", "firefox"),
+            // Subdirectories are part of the file ID
+            ("kde-dolphin", "system-file-manager"),
+            ("alacritty", "custom-terminal"),
+            ("kitty", "terminal"),
+            ("steam_app_570", "steam_icon_570"),
+            // Only unlocalized keys of the main group are used
+            ("localized", "localized"),
+            ("unknown", "unknown"),
+        ];
+        for (class, icon) in cases {
+            assert_eq!(resolver.resolve(class), icon, "{class}");
+        }
+    }
+
+    #[test]
+    fn missing_data_dirs() {
+        let dir = TempDir::new("desktop-entry-missing");
+        let mut resolver = resolver(&dir, &[]);
+        assert_eq!(resolver.resolve("Code"), "Code");
+    }
+}
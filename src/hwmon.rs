@@ -0,0 +1,244 @@
+use std::path::{Path, PathBuf};
+
+use crate::{config::SensorSelector, sysfs::read_to_string_buf};
+
+pub const DEFAULT_HWMON_PATH: &str = "/sys/class/hwmon";
+
+#[derive(Debug, Clone)]
+pub struct TemperatureSensor {
+    pub chip: String,
+    pub label: String,
+    /// Temperature in degrees Celsius
+    pub temperature: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct FanSensor {
+    pub chip: String,
+    pub label: String,
+    pub rpm: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HwmonInfo {
+    pub temperatures: Vec<TemperatureSensor>,
+    pub fans: Vec<FanSensor>,
+}
+
+/// Every sensor is selected if no selectors are configured
+pub fn is_selected(selectors: &[SensorSelector], chip: &str, label: &str) -> bool {
+    selectors.is_empty()
+        || selectors.iter().any(|selector| {
+            selector.chip == chip
+                && selector
+                    .label
+                    .as_ref()
+                    .is_none_or(|selected| selected == label)
+        })
+}
+
+fn read_trimmed(path: &Path, buffer: &mut String) -> Option<String> {
+    read_to_string_buf(path, buffer)
+        .ok()
+        .map(|str| str.trim_end_matches('\n').to_owned())
+}
+
+/// Returns sorted `(index, input path)` pairs of `{prefix}N_input` files in a hwmon directory
+fn sensor_inputs(hwmon_dir: &Path, prefix: &str) -> Vec<(u32, PathBuf)> {
+    let Ok(entries) = hwmon_dir.read_dir() else {
+        return Vec::new();
+    };
+
+    let mut inputs = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let file_name = path.file_name()?.to_str()?;
+            let index = file_name
+                .strip_prefix(prefix)?
+                .strip_suffix("_input")?
+                .parse::<u32>()
+                .ok()?;
+            Some((index, path))
+        })
+        .collect::<Vec<_>>();
+    inputs.sort_unstable_by_key(|(index, _)| *index);
+
+    inputs
+}
+
+// https://www.kernel.org/doc/html/latest/hwmon/sysfs-interface.html
+pub fn get_sensors<P: AsRef<Path>>(hwmon_path: P) -> HwmonInfo {
+    let mut info = HwmonInfo::default();
+
+    let Ok(entries) = hwmon_path.as_ref().read_dir() else {
+        return info;
+    };
+
+    let mut hwmon_dirs = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    hwmon_dirs.sort_unstable();
+
+    let mut buffer = String::with_capacity(16);
+    for hwmon_dir in hwmon_dirs {
+        let chip = read_trimmed(&hwmon_dir.join("name"), &mut buffer).unwrap_or_else(|| {
+            hwmon_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+        for (index, input_path) in sensor_inputs(&hwmon_dir, "temp") {
+            // Some sensors fail to read with EIO when they're powered down
+            let Some(temperature) =
+                read_trimmed(&input_path, &mut buffer).and_then(|value| value.parse::<i64>().ok())
+            else {
+                continue;
+            };
+
+            let label = read_trimmed(&hwmon_dir.join(format!("temp{index}_label")), &mut buffer)
+                .unwrap_or_else(|| format!("temp{index}"));
+
+            info.temperatures.push(TemperatureSensor {
+                chip: chip.clone(),
+                label,
+                temperature: temperature as f32 / 1000.0,
+            });
+        }
+
+        for (index, input_path) in sensor_inputs(&hwmon_dir, "fan") {
+            let Some(rpm) =
+                read_trimmed(&input_path, &mut buffer).and_then(|value| value.parse::<u32>().ok())
+            else {
+                continue;
+            };
+
+            let label = read_trimmed(&hwmon_dir.join(format!("fan{index}_label")), &mut buffer)
+                .unwrap_or_else(|| format!("fan{index}"));
+
+            info.fans.push(FanSensor {
+                chip: chip.clone(),
+                label,
+                rpm,
+            });
+        }
+    }
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn chip(hwmon: &TempDir, dir: &str, files: &[(&str, &str)]) {
+        for (file, contents) in files {
+            hwmon.write(Path::new(dir).join(file), contents);
+        }
+    }
+
+    fn selector(chip: &str, label: Option<&str>) -> SensorSelector {
+        SensorSelector {
+            chip: chip.to_string(),
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn sensors() {
+        let hwmon = TempDir::new("hwmon-sensors");
+        chip(
+            &hwmon,
+            "hwmon0",
+            &[
+                ("name", "k10temp\n"),
+                ("temp1_input", "45250\n"),
+                ("temp1_label", "Tctl\n"),
+                ("temp3_input", "38000\n"),
+                ("temp3_label", "Tccd1\n"),
+            ],
+        );
+        chip(
+            &hwmon,
+            "hwmon1",
+            &[
+                ("name", "nct6775\n"),
+                ("temp1_input", "30000\n"),
+                ("fan2_input", "1200\n"),
+                ("fan2_label", "CPU\n"),
+                ("fan10_input", "800\n"),
+            ],
+        );
+        // Chips without a name use the directory name
+        chip(&hwmon, "hwmon2", &[("temp1_input", "-5000\n")]);
+
+        let info = get_sensors(hwmon.path());
+        let temperatures = info
+            .temperatures
+            .iter()
+            .map(|sensor| {
+                (
+                    sensor.chip.as_str(),
+                    sensor.label.as_str(),
+                    sensor.temperature,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            temperatures,
+            [
+                ("k10temp", "Tctl", 45.25),
+                ("k10temp", "Tccd1", 38.0),
+                ("nct6775", "temp1", 30.0),
+                ("hwmon2", "temp1", -5.0),
+            ]
+        );
+
+        let fans = info
+            .fans
+            .iter()
+            .map(|fan| (fan.chip.as_str(), fan.label.as_str(), fan.rpm))
+            .collect::<Vec<_>>();
+        assert_eq!(fans, [("nct6775", "CPU", 1200), ("nct6775", "fan10", 800)]);
+    }
+
+    #[test]
+    fn unreadable_inputs() {
+        let hwmon = TempDir::new("hwmon-unreadable");
+        chip(
+            &hwmon,
+            "hwmon0",
+            &[
+                ("name", "amdgpu\n"),
+                ("temp1_input", ""),
+                ("temp2_input", "50000\n"),
+                ("fan1_input", "N/A\n"),
+            ],
+        );
+
+        let info = get_sensors(hwmon.path());
+        assert_eq!(info.temperatures.len(), 1);
+        assert_eq!(info.temperatures[0].label, "temp2");
+        assert!(info.fans.is_empty());
+    }
+
+    #[test]
+    fn missing_path() {
+        let info = get_sensors("/nonexistent/hwmon");
+        assert!(info.temperatures.is_empty());
+        assert!(info.fans.is_empty());
+    }
+
+    #[test]
+    fn selection() {
+        assert!(is_selected(&[], "k10temp", "Tctl"));
+
+        let selectors = [selector("k10temp", Some("Tctl")), selector("nct6775", None)];
+        assert!(is_selected(&selectors, "k10temp", "Tctl"));
+        assert!(!is_selected(&selectors, "k10temp", "Tccd1"));
+        assert!(is_selected(&selectors, "nct6775", "temp1"));
+        assert!(is_selected(&selectors, "nct6775", "CPU"));
+        assert!(!is_selected(&selectors, "amdgpu", "edge"));
+    }
+}
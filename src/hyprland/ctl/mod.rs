@@ -43,22 +43,35 @@ struct Devices {
     keyboards: Vec<Keyboard>,
 }
 
-pub async fn get_main_keyboard() -> anyhow::Result<Keyboard> {
+/// Returns the keyboard with the given name, or the main keyboard if no name is given
+pub async fn get_keyboard(name: Option<&str>) -> anyhow::Result<Keyboard> {
     let response = request("j/devices").await?;
 
     let devices: Devices = serde_json::from_str(&response)?;
-    let main_keyboard = devices
+    let keyboard = devices
         .keyboards
         .into_iter()
-        .find(|kb| kb.main)
-        .expect("No main keyboard found");
+        .find(|kb| name.map_or(kb.main, |name| kb.name == name));
 
-    Ok(main_keyboard)
+    match (keyboard, name) {
+        (Some(keyboard), _) => Ok(keyboard),
+        (None, Some(name)) => anyhow::bail!("Keyboard {name} not found"),
+        (None, None) => anyhow::bail!("No main keyboard found"),
+    }
+}
+
+pub async fn switch_xkb_layout(keyboard: &str, layout: &str) -> anyhow::Result<()> {
+    let response = request(&format!("switchxkblayout {keyboard} {layout}")).await?;
+    if response != "ok" {
+        anyhow::bail!("Failed to switch layout: {response}");
+    }
+
+    Ok(())
 }
 
 #[derive(Deserialize)]
 pub struct Workspace {
-    pub id: usize,
+    pub id: i64,
     pub name: String,
     pub monitor: String,
     pub windows: u64,
@@ -73,3 +86,40 @@ pub async fn get_workspaces() -> anyhow::Result<Vec<Workspace>> {
     let workspaces: Vec<Workspace> = serde_json::from_str(&response)?;
     Ok(workspaces)
 }
+
+#[derive(Deserialize)]
+pub struct WorkspaceRef {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub name: String,
+    pub focused: bool,
+    pub active_workspace: WorkspaceRef,
+    pub special_workspace: WorkspaceRef,
+}
+
+pub async fn get_monitors() -> anyhow::Result<Vec<Monitor>> {
+    let response = request("j/monitors").await?;
+
+    let monitors: Vec<Monitor> = serde_json::from_str(&response)?;
+    Ok(monitors)
+}
+
+#[derive(Deserialize)]
+pub struct Client {
+    pub address: String,
+    pub mapped: bool,
+    pub workspace: WorkspaceRef,
+    pub class: String,
+}
+
+pub async fn get_clients() -> anyhow::Result<Vec<Client>> {
+    let response = request("j/clients").await?;
+
+    let clients: Vec<Client> = serde_json::from_str(&response)?;
+    Ok(clients)
+}
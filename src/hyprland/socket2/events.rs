@@ -1,11 +1,59 @@
 #[derive(Debug, Clone)]
 pub enum HyprlandEvent {
-    WorkspaceV2 { id: usize, name: String },
-    ActiveWindow { class: String, title: String },
-    CreateWorkspaceV2 { id: usize, name: String },
-    DestroyWorkspaceV2 { id: usize, name: String },
-    RenameWorkspace { id: usize, new_name: String },
-    ActiveLayout { name: String, layout: String },
+    WorkspaceV2 {
+        id: i64,
+        name: String,
+    },
+    ActiveWindow {
+        class: String,
+        title: String,
+    },
+    CreateWorkspaceV2 {
+        id: i64,
+        name: String,
+    },
+    DestroyWorkspaceV2 {
+        id: i64,
+        name: String,
+    },
+    RenameWorkspace {
+        id: i64,
+        new_name: String,
+    },
+    ActiveLayout {
+        name: String,
+        layout: String,
+    },
+    Submap {
+        name: String,
+    },
+    ActiveSpecial {
+        name: String,
+        monitor: String,
+    },
+    FocusedMon {
+        monitor: String,
+        workspace_name: String,
+    },
+    MoveWorkspaceV2 {
+        id: i64,
+        monitor: String,
+    },
+    OpenWindow {
+        address: String,
+        workspace_name: String,
+        class: String,
+    },
+    CloseWindow {
+        address: String,
+    },
+    Urgent {
+        address: String,
+    },
+    MoveWindowV2 {
+        address: String,
+        workspace_id: i64,
+    },
 }
 
 impl HyprlandEvent {
@@ -15,7 +63,7 @@ impl HyprlandEvent {
             "workspacev2" => {
                 let (id, name) = data.split_once(',').expect("Invalid event");
                 Ok(Self::WorkspaceV2 {
-                    id: usize::from_str_radix(id, 16)?,
+                    id: id.parse()?,
                     name: name.to_string(),
                 })
             }
@@ -29,21 +77,21 @@ impl HyprlandEvent {
             "createworkspacev2" => {
                 let (id, name) = data.split_once(',').expect("Invalid event");
                 Ok(Self::CreateWorkspaceV2 {
-                    id: usize::from_str_radix(id, 16)?,
+                    id: id.parse()?,
                     name: name.to_string(),
                 })
             }
             "destroyworkspacev2" => {
                 let (id, name) = data.split_once(',').expect("Invalid event");
                 Ok(Self::DestroyWorkspaceV2 {
-                    id: usize::from_str_radix(id, 16)?,
+                    id: id.parse()?,
                     name: name.to_string(),
                 })
             }
             "renameworkspace" => {
                 let (id, new_name) = data.split_once(',').expect("Invalid event");
                 Ok(Self::RenameWorkspace {
-                    id: usize::from_str_radix(id, 16)?,
+                    id: id.parse()?,
                     new_name: new_name.to_string(),
                 })
             }
@@ -54,6 +102,61 @@ impl HyprlandEvent {
                     layout: layout.to_string(),
                 })
             }
+            "activespecial" => {
+                let (name, monitor) = data.split_once(',').expect("Invalid event");
+                Ok(Self::ActiveSpecial {
+                    name: name.to_string(),
+                    monitor: monitor.to_string(),
+                })
+            }
+            "focusedmon" => {
+                let (monitor, workspace_name) = data.split_once(',').expect("Invalid event");
+                Ok(Self::FocusedMon {
+                    monitor: monitor.to_string(),
+                    workspace_name: workspace_name.to_string(),
+                })
+            }
+            "moveworkspacev2" => {
+                // The name in between may contain commas
+                let (id, data) = data.split_once(',').expect("Invalid event");
+                let (_, monitor) = data.rsplit_once(',').expect("Invalid event");
+                Ok(Self::MoveWorkspaceV2 {
+                    id: id.parse()?,
+                    monitor: monitor.to_string(),
+                })
+            }
+            "openwindow" => {
+                // The title is left out, it's last and may contain commas
+                let mut parts = data.splitn(4, ',');
+                let (Some(address), Some(workspace_name), Some(class)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    anyhow::bail!("Invalid event: {value}");
+                };
+                Ok(Self::OpenWindow {
+                    address: address.to_string(),
+                    workspace_name: workspace_name.to_string(),
+                    class: class.to_string(),
+                })
+            }
+            "closewindow" => Ok(Self::CloseWindow {
+                address: data.to_string(),
+            }),
+            "urgent" => Ok(Self::Urgent {
+                address: data.to_string(),
+            }),
+            "movewindowv2" => {
+                // The workspace name is left out, it's last and may contain commas
+                let (address, data) = data.split_once(',').expect("Invalid event");
+                let (workspace_id, _) = data.split_once(',').expect("Invalid event");
+                Ok(Self::MoveWindowV2 {
+                    address: address.to_string(),
+                    workspace_id: workspace_id.parse()?,
+                })
+            }
+            "submap" => Ok(Self::Submap {
+                name: data.to_string(),
+            }),
             _ => anyhow::bail!("Unknown event: {}", event_name),
         }
     }
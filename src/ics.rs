@@ -0,0 +1,909 @@
+use chrono::{
+    DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Recurring events are expanded this far into the past and the future
+const RECURRENCE_WINDOW: TimeDelta = TimeDelta::days(366);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub summary: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub all_day: bool,
+}
+
+/// Joins folded content lines
+/// https://datatracker.ietf.org/doc/html/rfc5545#section-3.1
+fn unfold(data: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in data.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if let Some(continuation) = line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(continuation);
+                continue;
+            }
+        }
+
+        lines.push(line.to_string());
+    }
+
+    lines
+}
+
+struct Property<'a> {
+    name: &'a str,
+    params: Vec<(&'a str, &'a str)>,
+    value: &'a str,
+}
+
+/// Splits a content line into the property name, parameters and value
+fn split_property(line: &str) -> Option<Property<'_>> {
+    // Parameter values may be quoted and contain ':' or ';'
+    let mut in_quotes = false;
+    let value_start = line.char_indices().find_map(|(idx, char)| match char {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(idx),
+        _ => None,
+    })?;
+
+    let (name_params, value) = (&line[..value_start], &line[value_start + 1..]);
+    let mut parts = name_params.split(';');
+    let name = parts.next()?;
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key, value.trim_matches('"')))
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value,
+    })
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            text.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(char) => text.push(char),
+            None => {}
+        }
+    }
+
+    text
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Utc,
+    Tz(Tz),
+    /// Dates, floating times and unknown TZIDs
+    Local,
+}
+
+/// A DATE or DATE-TIME value in its own timezone, recurrences are expanded in it to keep the
+/// wall-clock time across DST changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DateValue {
+    time: NaiveDateTime,
+    zone: Zone,
+    is_date: bool,
+}
+
+impl DateValue {
+    fn parse(params: &[(&str, &str)], value: &str) -> Option<Self> {
+        let is_date = params
+            .iter()
+            .any(|(key, value)| *key == "VALUE" && *value == "DATE")
+            || value.len() == 8;
+
+        if is_date {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+            return Some(Self {
+                time: date.and_time(NaiveTime::MIN),
+                zone: Zone::Local,
+                is_date: true,
+            });
+        }
+
+        if let Some(value) = value.strip_suffix('Z') {
+            let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+            return Some(Self {
+                time,
+                zone: Zone::Utc,
+                is_date: false,
+            });
+        }
+
+        let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        let zone = params
+            .iter()
+            .find(|(key, _)| *key == "TZID")
+            .and_then(|(_, tzid)| tzid.parse::<Tz>().ok())
+            .map_or(Zone::Local, Zone::Tz);
+
+        Some(Self {
+            time,
+            zone,
+            is_date: false,
+        })
+    }
+
+    fn resolve(&self) -> Option<DateTime<Local>> {
+        match self.zone {
+            Zone::Utc => Some(Utc.from_utc_datetime(&self.time).with_timezone(&Local)),
+            Zone::Tz(timezone) => Some(
+                timezone
+                    .from_local_datetime(&self.time)
+                    .earliest()?
+                    .with_timezone(&Local),
+            ),
+            Zone::Local => Local.from_local_datetime(&self.time).earliest(),
+        }
+    }
+}
+
+/// Parses a DATE or DATE-TIME value, returning the time and whether it's a date
+fn parse_date_time(params: &[(&str, &str)], value: &str) -> Option<(DateTime<Local>, bool)> {
+    let value = DateValue::parse(params, value)?;
+    Some((value.resolve()?, value.is_date))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// Parses a BYDAY weekday, e.g. `MO` or `-1FR` for the last Friday of the month
+fn parse_weekday(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let (ordinal, weekday) = value.split_at_checked(value.len().checked_sub(2)?)?;
+    let weekday = match weekday {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(ordinal.parse().ok()?),
+    };
+
+    Some((ordinal, weekday))
+}
+
+/// A recurrence rule
+/// https://datatracker.ietf.org/doc/html/rfc5545#section-3.3.10
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    /// Inclusive
+    until: Option<DateTime<Local>>,
+    /// Weekdays with their ordinal in the month for monthly rules
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month, negative ones count from the end
+    by_month_day: Vec<i32>,
+}
+
+impl Rule {
+    /// Returns `None` for rules with parts that aren't supported
+    fn parse(value: &str) -> Option<Self> {
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut by_day = Vec::new();
+        let mut by_month_day = Vec::new();
+
+        for part in value.split(';') {
+            let (key, value) = part.split_once('=')?;
+            match key {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return None,
+                    });
+                }
+                "INTERVAL" => interval = value.parse().ok().filter(|interval| *interval > 0)?,
+                "COUNT" => count = Some(value.parse().ok()?),
+                "UNTIL" => {
+                    let mut value = DateValue::parse(&[], value)?;
+                    // Dates include the whole day
+                    if value.is_date {
+                        value.time += TimeDelta::days(1) - TimeDelta::seconds(1);
+                    }
+                    until = Some(value.resolve()?);
+                }
+                "BYDAY" => {
+                    by_day = value.split(',').map(parse_weekday).collect::<Option<_>>()?;
+                }
+                "BYMONTHDAY" => {
+                    by_month_day = value
+                        .split(',')
+                        .map(|day| day.parse().ok())
+                        .collect::<Option<_>>()?;
+                }
+                // Weeks always start on Monday
+                "WKST" => {}
+                _ => return None,
+            }
+        }
+
+        let frequency = frequency?;
+        let supported = match frequency {
+            Frequency::Daily | Frequency::Weekly => {
+                by_day.iter().all(|(ordinal, _)| ordinal.is_none()) && by_month_day.is_empty()
+            }
+            Frequency::Monthly => true,
+            Frequency::Yearly => by_day.is_empty() && by_month_day.is_empty(),
+        };
+
+        supported.then_some(Self {
+            frequency,
+            interval,
+            count,
+            until,
+            by_day,
+            by_month_day,
+        })
+    }
+
+    /// First day of the `period`th period after the one containing `start`
+    fn period_start(&self, start: NaiveDate, period: u32) -> Option<NaiveDate> {
+        let steps = period.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => start.checked_add_days(Days::new(steps.into())),
+            Frequency::Weekly => start
+                .checked_sub_days(Days::new(start.weekday().num_days_from_monday().into()))?
+                .checked_add_days(Days::new(u64::from(steps) * 7)),
+            Frequency::Monthly => start.with_day(1)?.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => start
+                .with_day(1)?
+                .with_month(1)?
+                .checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+
+    /// Dates of the occurrences in the period starting at `period_start`, sorted
+    fn period_dates(&self, period_start: NaiveDate, start: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = match self.frequency {
+            Frequency::Daily => vec![period_start],
+            Frequency::Weekly if self.by_day.is_empty() => period_start
+                .checked_add_days(Days::new(start.weekday().num_days_from_monday().into()))
+                .into_iter()
+                .collect(),
+            Frequency::Weekly => self
+                .by_day
+                .iter()
+                .filter_map(|(_, weekday)| {
+                    period_start.checked_add_days(Days::new(weekday.num_days_from_monday().into()))
+                })
+                .collect(),
+            Frequency::Monthly if self.by_day.is_empty() && self.by_month_day.is_empty() => {
+                // Months without the day are skipped
+                period_start.with_day(start.day()).into_iter().collect()
+            }
+            Frequency::Monthly => {
+                let days = period_start
+                    .checked_add_months(Months::new(1))
+                    .map_or(31, |next| (next - period_start).num_days());
+                period_start
+                    .iter_days()
+                    .take_while(|date| date.month() == period_start.month())
+                    .filter(|date| self.matches_month(*date, days))
+                    .collect()
+            }
+            Frequency::Yearly => {
+                NaiveDate::from_ymd_opt(period_start.year(), start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        if self.frequency == Frequency::Daily && !self.by_day.is_empty() {
+            dates.retain(|date| {
+                self.by_day
+                    .iter()
+                    .any(|(_, weekday)| date.weekday() == *weekday)
+            });
+        }
+
+        dates.sort_unstable();
+        dates.dedup();
+        dates
+    }
+
+    /// Checks BYDAY and BYMONTHDAY of monthly rules, `days` is the length of the month
+    fn matches_month(&self, date: NaiveDate, days: i64) -> bool {
+        let day = i64::from(date.day());
+
+        let month_day = self.by_month_day.is_empty()
+            || self.by_month_day.iter().any(|month_day| {
+                let month_day = i64::from(*month_day);
+                month_day == day || month_day < 0 && days + 1 + month_day == day
+            });
+
+        let weekday = self.by_day.is_empty()
+            || self.by_day.iter().any(|(ordinal, weekday)| {
+                date.weekday() == *weekday
+                    && match ordinal.map(i64::from) {
+                        None => true,
+                        Some(ordinal) if ordinal > 0 => (day - 1) / 7 + 1 == ordinal,
+                        Some(ordinal) => (days - day) / 7 + 1 == -ordinal,
+                    }
+            });
+
+        month_day && weekday
+    }
+
+    /// Starts of the occurrences that begin before `to`
+    fn occurrences(&self, start: DateValue, to: DateTime<Local>) -> Vec<DateTime<Local>> {
+        let mut occurrences = Vec::new();
+
+        // The start is always the first occurrence, even if it doesn't match the rule
+        let Some(first) = start.resolve() else {
+            return occurrences;
+        };
+        if first >= to {
+            return occurrences;
+        }
+        occurrences.push(first);
+
+        // A day of slack for the difference between the timezones
+        let Some(last_day) = to.date_naive().succ_opt() else {
+            return occurrences;
+        };
+        let start_date = start.time.date();
+
+        let mut period = 0;
+        while let Some(period_start) = self.period_start(start_date, period) {
+            if period_start > last_day {
+                break;
+            }
+
+            for date in self.period_dates(period_start, start_date) {
+                if date <= start_date {
+                    continue;
+                }
+
+                let occurrence = DateValue {
+                    time: date.and_time(start.time.time()),
+                    ..start
+                };
+                // Times skipped by DST changes don't occur
+                let Some(occurrence) = occurrence.resolve() else {
+                    continue;
+                };
+
+                let count = u32::try_from(occurrences.len()).unwrap_or(u32::MAX);
+                if occurrence >= to
+                    || self.until.is_some_and(|until| occurrence > until)
+                    || self.count.is_some_and(|max| count >= max)
+                {
+                    return occurrences;
+                }
+
+                occurrences.push(occurrence);
+            }
+
+            period += 1;
+        }
+
+        occurrences
+    }
+}
+
+/// Properties of a VEVENT
+#[derive(Default)]
+struct Component {
+    uid: String,
+    summary: String,
+    start: Option<DateValue>,
+    end: Option<DateTime<Local>>,
+    duration: Option<TimeDelta>,
+    rule: Option<Rule>,
+    exdates: Vec<DateTime<Local>>,
+    /// Set on occurrences of a recurring event that were changed
+    recurrence_id: Option<DateTime<Local>>,
+}
+
+/// Parses VEVENTs from an iCalendar file.
+/// Recurring events are expanded into their occurrences that overlap `from..to`. Rules with
+/// parts other than `BYDAY` and `BYMONTHDAY` only return their first occurrence.
+pub fn parse_events(data: &str, from: DateTime<Local>, to: DateTime<Local>) -> Vec<Event> {
+    let mut components = Vec::new();
+
+    let mut component = None;
+    // Depth of components nested in the event, e.g. VALARM
+    let mut nested = 0usize;
+
+    for line in unfold(data) {
+        let Some(Property {
+            name,
+            params,
+            value,
+        }) = split_property(&line)
+        else {
+            continue;
+        };
+
+        match (name, value, &mut component) {
+            ("BEGIN", _, Some(_)) => nested += 1,
+            ("END", _, _) if nested != 0 => nested -= 1,
+            (_, _, _) if nested != 0 => {}
+            ("BEGIN", "VEVENT", None) => component = Some(Component::default()),
+            ("END", "VEVENT", Some(_)) => components.extend(component.take()),
+            ("UID", _, Some(component)) => component.uid = value.to_string(),
+            ("SUMMARY", _, Some(component)) => component.summary = unescape_text(value),
+            ("DTSTART", _, Some(component)) => {
+                component.start = DateValue::parse(&params, value);
+            }
+            ("DTEND", _, Some(component)) => {
+                component.end = parse_date_time(&params, value).map(|(end, _)| end);
+            }
+            ("DURATION", _, Some(component)) => component.duration = parse_duration(value),
+            ("RRULE", _, Some(component)) => component.rule = Rule::parse(value),
+            ("EXDATE", _, Some(component)) => component.exdates.extend(
+                value
+                    .split(',')
+                    .filter_map(|value| parse_date_time(&params, value))
+                    .map(|(exdate, _)| exdate),
+            ),
+            ("RECURRENCE-ID", _, Some(component)) => {
+                component.recurrence_id = parse_date_time(&params, value).map(|(id, _)| id);
+            }
+            _ => {}
+        }
+    }
+
+    // Changed occurrences replace the ones of the rule
+    let overrides = components
+        .iter()
+        .filter_map(|component| Some((component.uid.as_str(), component.recurrence_id?)))
+        .collect::<Vec<_>>();
+
+    let mut events = Vec::new();
+    for component in &components {
+        let Some(start_value) = component.start else {
+            continue;
+        };
+        let Some(start) = start_value.resolve() else {
+            continue;
+        };
+        let all_day = start_value.is_date;
+
+        let end = component.end.unwrap_or_else(|| {
+            // https://datatracker.ietf.org/doc/html/rfc5545#section-3.6.1
+            start
+                + component.duration.unwrap_or(if all_day {
+                    TimeDelta::days(1)
+                } else {
+                    TimeDelta::zero()
+                })
+        });
+
+        let event = |start: DateTime<Local>, end: DateTime<Local>| Event {
+            summary: component.summary.clone(),
+            start,
+            end,
+            all_day,
+        };
+
+        let rule = component
+            .rule
+            .as_ref()
+            .filter(|_| component.recurrence_id.is_none());
+        let Some(rule) = rule else {
+            events.push(event(start, end));
+            continue;
+        };
+
+        let duration = end - start;
+        events.extend(
+            rule.occurrences(start_value, to)
+                .into_iter()
+                .filter(|occurrence| {
+                    *occurrence + duration > from
+                        && !component.exdates.contains(occurrence)
+                        && !overrides.contains(&(component.uid.as_str(), *occurrence))
+                })
+                .map(|occurrence| event(occurrence, occurrence + duration)),
+        );
+    }
+
+    events
+}
+
+/// Parses a DURATION value, e.g. `PT1H30M` or `P1D`
+fn parse_duration(value: &str) -> Option<TimeDelta> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+
+    let mut duration = TimeDelta::zero();
+    let mut number = String::new();
+    for char in value.strip_prefix('P')?.chars() {
+        match char {
+            '0'..='9' => number.push(char),
+            'T' => {}
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let count = number.parse::<i64>().ok()?;
+                number.clear();
+
+                duration += match char {
+                    'W' => TimeDelta::weeks(count),
+                    'D' => TimeDelta::days(count),
+                    'H' => TimeDelta::hours(count),
+                    'M' => TimeDelta::minutes(count),
+                    _ => TimeDelta::seconds(count),
+                };
+            }
+            _ => return None,
+        }
+    }
+
+    Some(if negative { -duration } else { duration })
+}
+
+fn collect_ics_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return;
+    }
+
+    let Ok(entries) = path.read_dir() else {
+        return;
+    };
+
+    // vdirsyncer stores every collection in its own subdirectory
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_ics_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "ics") {
+            files.push(path);
+        }
+    }
+}
+
+/// Loads events from `.ics` files and directories, sorted by start time
+pub fn load_events(paths: &[PathBuf]) -> Vec<Event> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_ics_files(path, &mut files);
+    }
+
+    let now = Local::now();
+    let (from, to) = (now - RECURRENCE_WINDOW, now + RECURRENCE_WINDOW);
+
+    let mut events = files
+        .into_iter()
+        .filter_map(|file| match fs::read_to_string(&file) {
+            Ok(data) => Some(parse_events(&data, from, to)),
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", file.display());
+                None
+            }
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    events.sort_by_key(|event| event.start);
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    fn calendar(event: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\n{event}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")
+    }
+
+    fn starts(data: &str) -> Vec<DateTime<Local>> {
+        parse_events(data, utc(2000, 1, 1, 0, 0), utc(2030, 1, 1, 0, 0))
+            .into_iter()
+            .map(|event| event.start)
+            .collect()
+    }
+
+    #[test]
+    fn unfold_lines() {
+        assert_eq!(
+            unfold("SUMMARY:Long\r\n  meeting\r\n\tname\r\nUID:1\r\n"),
+            vec!["SUMMARY:Long meetingname", "UID:1"]
+        );
+        assert_eq!(unfold(" leading\nUID:1"), vec![" leading", "UID:1"]);
+    }
+
+    #[test]
+    fn split_properties() {
+        let property =
+            split_property(r#"DTSTART;TZID="Europe/Berlin";VALUE=DATE-TIME:20240115T100000"#)
+                .unwrap();
+        assert_eq!(property.name, "DTSTART");
+        assert_eq!(
+            property.params,
+            vec![("TZID", "Europe/Berlin"), ("VALUE", "DATE-TIME")]
+        );
+        assert_eq!(property.value, "20240115T100000");
+
+        let property = split_property(r#"ATTENDEE;CN="Doe: John":mailto:a@b.c"#).unwrap();
+        assert_eq!(property.params, vec![("CN", "Doe: John")]);
+        assert_eq!(property.value, "mailto:a@b.c");
+
+        assert!(split_property("no value").is_none());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(TimeDelta::minutes(90)));
+        assert_eq!(parse_duration("P1D"), Some(TimeDelta::days(1)));
+        assert_eq!(parse_duration("P2W"), Some(TimeDelta::weeks(2)));
+        assert_eq!(parse_duration("-PT15M"), Some(TimeDelta::minutes(-15)));
+        assert_eq!(
+            parse_duration("+P1DT2H3S"),
+            Some(TimeDelta::days(1) + TimeDelta::hours(2) + TimeDelta::seconds(3))
+        );
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("PT1X"), None);
+    }
+
+    #[test]
+    fn date_times() {
+        assert_eq!(
+            parse_date_time(&[("TZID", "Europe/Berlin")], "20240115T100000"),
+            Some((utc(2024, 1, 15, 9, 0), false))
+        );
+        assert_eq!(
+            parse_date_time(&[], "20240115T100000Z"),
+            Some((utc(2024, 1, 15, 10, 0), false))
+        );
+
+        let local = Local
+            .with_ymd_and_hms(2024, 1, 15, 10, 0, 0)
+            .earliest()
+            .unwrap();
+        assert_eq!(
+            parse_date_time(&[], "20240115T100000"),
+            Some((local, false))
+        );
+        assert_eq!(
+            parse_date_time(&[("TZID", "Nowhere/Unknown")], "20240115T100000"),
+            Some((local, false))
+        );
+
+        let midnight = Local
+            .with_ymd_and_hms(2024, 1, 15, 0, 0, 0)
+            .earliest()
+            .unwrap();
+        assert_eq!(
+            parse_date_time(&[("VALUE", "DATE")], "20240115"),
+            Some((midnight, true))
+        );
+        assert_eq!(parse_date_time(&[], "20240115"), Some((midnight, true)));
+
+        assert_eq!(parse_date_time(&[], "2024-01-15"), None);
+    }
+
+    #[test]
+    fn single_event() {
+        let events = parse_events(
+            &calendar(
+                "SUMMARY:Lunch\\, team\r\nDTSTART:20240115T120000Z\r\nDURATION:PT1H\r\n\
+                 BEGIN:VALARM\r\nSUMMARY:Alarm\r\nEND:VALARM",
+            ),
+            utc(2030, 1, 1, 0, 0),
+            utc(2031, 1, 1, 0, 0),
+        );
+
+        // Events without a rule are kept outside of the window
+        assert_eq!(
+            events,
+            vec![Event {
+                summary: "Lunch, team".to_string(),
+                start: utc(2024, 1, 15, 12, 0),
+                end: utc(2024, 1, 15, 13, 0),
+                all_day: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn weekly_count_keeps_wall_clock_time() {
+        // Daylight saving time starts on March 31 in Berlin
+        let starts = starts(&calendar(
+            "DTSTART;TZID=Europe/Berlin:20240325T100000\r\nRRULE:FREQ=WEEKLY;COUNT=3",
+        ));
+
+        assert_eq!(
+            starts,
+            vec![
+                utc(2024, 3, 25, 9, 0),
+                utc(2024, 4, 1, 8, 0),
+                utc(2024, 4, 8, 8, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_by_day_with_interval() {
+        let starts = starts(&calendar(
+            "DTSTART:20240101T090000Z\r\nRRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=5",
+        ));
+
+        assert_eq!(
+            starts,
+            vec![
+                utc(2024, 1, 1, 9, 0),
+                utc(2024, 1, 3, 9, 0),
+                utc(2024, 1, 15, 9, 0),
+                utc(2024, 1, 17, 9, 0),
+                utc(2024, 1, 29, 9, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_until_with_exdate() {
+        let starts = starts(&calendar(
+            "DTSTART:20240101T090000Z\r\nRRULE:FREQ=DAILY;UNTIL=20240105T090000Z\r\n\
+             EXDATE:20240102T090000Z,20240104T090000Z",
+        ));
+
+        assert_eq!(
+            starts,
+            vec![
+                utc(2024, 1, 1, 9, 0),
+                utc(2024, 1, 3, 9, 0),
+                utc(2024, 1, 5, 9, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_rules() {
+        let last_friday = starts(&calendar(
+            "DTSTART:20240126T090000Z\r\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3",
+        ));
+        assert_eq!(
+            last_friday,
+            vec![
+                utc(2024, 1, 26, 9, 0),
+                utc(2024, 2, 23, 9, 0),
+                utc(2024, 3, 29, 9, 0)
+            ]
+        );
+
+        // Months without a 31st are skipped
+        let day = starts(&calendar(
+            "DTSTART:20240131T090000Z\r\nRRULE:FREQ=MONTHLY;COUNT=3",
+        ));
+        assert_eq!(
+            day,
+            vec![
+                utc(2024, 1, 31, 9, 0),
+                utc(2024, 3, 31, 9, 0),
+                utc(2024, 5, 31, 9, 0)
+            ]
+        );
+
+        let last_day = starts(&calendar(
+            "DTSTART:20240131T090000Z\r\nRRULE:FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=2",
+        ));
+        assert_eq!(
+            last_day,
+            vec![utc(2024, 1, 31, 9, 0), utc(2024, 2, 29, 9, 0)]
+        );
+    }
+
+    #[test]
+    fn yearly_all_day() {
+        let events = parse_events(
+            &calendar("DTSTART;VALUE=DATE:20240229\r\nRRULE:FREQ=YEARLY;UNTIL=20320301"),
+            utc(2000, 1, 1, 0, 0),
+            utc(2040, 1, 1, 0, 0),
+        );
+
+        let days = events
+            .iter()
+            .map(|event| (event.start.date_naive(), event.all_day))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            days,
+            [2024, 2028, 2032].map(|year| (NaiveDate::from_ymd_opt(year, 2, 29).unwrap(), true))
+        );
+        assert_eq!(events[0].end - events[0].start, TimeDelta::days(1));
+    }
+
+    #[test]
+    fn endless_rules_stop_at_the_window() {
+        let events = parse_events(
+            &calendar("DTSTART:20200101T090000Z\r\nDTEND:20200101T100000Z\r\nRRULE:FREQ=DAILY"),
+            utc(2024, 1, 1, 9, 30),
+            utc(2024, 1, 3, 9, 0),
+        );
+
+        let starts = events.iter().map(|event| event.start).collect::<Vec<_>>();
+        assert_eq!(starts, vec![utc(2024, 1, 1, 9, 0), utc(2024, 1, 2, 9, 0)]);
+        assert_eq!(events[0].end, utc(2024, 1, 1, 10, 0));
+    }
+
+    #[test]
+    fn changed_occurrences() {
+        let data = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Standup\r\nDTSTART:20240101T090000Z\r\n\
+            RRULE:FREQ=DAILY;COUNT=3\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Late standup\r\n\
+            RECURRENCE-ID:20240102T090000Z\r\nDTSTART:20240102T110000Z\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let mut events = parse_events(data, utc(2000, 1, 1, 0, 0), utc(2030, 1, 1, 0, 0));
+        events.sort_by_key(|event| event.start);
+
+        let events = events
+            .iter()
+            .map(|event| (event.summary.as_str(), event.start))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                ("Standup", utc(2024, 1, 1, 9, 0)),
+                ("Late standup", utc(2024, 1, 2, 11, 0)),
+                ("Standup", utc(2024, 1, 3, 9, 0))
+            ]
+        );
+    }
+
+    #[test]
+    fn unsupported_rules() {
+        assert!(Rule::parse("FREQ=MONTHLY;BYDAY=MO,TU;BYSETPOS=-1").is_none());
+        assert!(Rule::parse("FREQ=HOURLY").is_none());
+        assert!(Rule::parse("FREQ=WEEKLY;BYDAY=1MO").is_none());
+        assert!(Rule::parse("FREQ=DAILY;INTERVAL=0").is_none());
+
+        // Only the first occurrence is returned
+        let starts = starts(&calendar(
+            "DTSTART:20240101T090000Z\r\nRRULE:FREQ=YEARLY;BYMONTH=1,2",
+        ));
+        assert_eq!(starts, vec![utc(2024, 1, 1, 9, 0)]);
+    }
+}
@@ -0,0 +1,55 @@
+use relm4::gtk::{
+    gio::{self, prelude::*, DBusCallFlags, DBusConnection, UnixFDList},
+    glib::{self, variant::Handle},
+};
+use std::os::fd::{FromRawFd, OwnedFd};
+
+// https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+const LOGIND_NAME: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+
+/// Logind idle inhibitor lock, released when dropped.
+///
+/// Only respected by idle daemons that ask logind, e.g. not by swayidle or hypridle without
+/// their logind integration.
+#[derive(Debug)]
+pub struct LogindInhibitor {
+    _fd: OwnedFd,
+}
+
+impl LogindInhibitor {
+    pub async fn new(connection: &DBusConnection, why: &str) -> Result<Self, glib::Error> {
+        let (reply, fd_list) = connection
+            .call_with_unix_fd_list_future(
+                Some(LOGIND_NAME),
+                MANAGER_PATH,
+                MANAGER_INTERFACE,
+                "Inhibit",
+                Some(&("idle", "crabbar", why, "block").to_variant()),
+                None,
+                DBusCallFlags::NONE,
+                -1,
+                None::<&UnixFDList>,
+            )
+            .await?;
+
+        let invalid_reply = || {
+            glib::Error::new(
+                gio::IOErrorEnum::InvalidData,
+                "Inhibit didn't return a file descriptor",
+            )
+        };
+
+        let Handle(idx) = reply
+            .child_value(0)
+            .get::<Handle>()
+            .ok_or_else(invalid_reply)?;
+        let fd = fd_list.ok_or_else(invalid_reply)?.get(idx)?;
+
+        // The list returns a duplicate that the caller owns
+        Ok(Self {
+            _fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+}
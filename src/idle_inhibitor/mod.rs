@@ -0,0 +1,17 @@
+mod logind;
+mod wayland;
+
+pub use logind::*;
+pub use wayland::*;
+
+/// Idle inhibitor, released when dropped
+#[derive(Debug)]
+// The inhibitors are only held for their drop
+#[allow(dead_code)]
+pub enum IdleInhibitor {
+    /// `zwp_idle_inhibitor_v1` on the bar surface, respected by the compositor and its idle
+    /// daemons
+    Wayland(WaylandInhibitor),
+    /// Fallback for compositors without the idle inhibit protocol
+    Logind(LogindInhibitor),
+}
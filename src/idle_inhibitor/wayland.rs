@@ -0,0 +1,103 @@
+use gdk4_wayland::{prelude::*, WaylandDisplay, WaylandSurface};
+use relm4::gtk::gdk;
+use wayland_client::{
+    delegate_noop,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::wl_registry::{self, WlRegistry},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+use wayland_protocols::wp::idle_inhibit::zv1::client::{
+    zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1, zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1,
+};
+
+// https://wayland.app/protocols/idle-inhibit-unstable-v1
+
+/// The protocol has no events, so the queue is never dispatched
+struct State;
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(State: ignore ZwpIdleInhibitManagerV1);
+delegate_noop!(State: ignore ZwpIdleInhibitorV1);
+
+fn flush(connection: &Connection) {
+    if let Err(err) = connection.flush() {
+        eprintln!("Failed to flush the Wayland connection: {err}");
+    }
+}
+
+/// `zwp_idle_inhibit_manager_v1` bound on the Wayland connection of GDK
+pub struct IdleInhibitManager {
+    connection: Connection,
+    /// Separate from the queue of GDK to not dispatch its events
+    queue: EventQueue<State>,
+    manager: ZwpIdleInhibitManagerV1,
+}
+
+impl IdleInhibitManager {
+    /// `None` if GDK doesn't use Wayland or the compositor doesn't support the protocol
+    pub fn new(display: &gdk::Display) -> Option<Self> {
+        let connection = display
+            .downcast_ref::<WaylandDisplay>()?
+            .wl_display()?
+            .backend()
+            .upgrade()
+            .map(Connection::from_backend)?;
+
+        let (globals, queue) = registry_queue_init::<State>(&connection)
+            .inspect_err(|err| eprintln!("Failed to get the Wayland globals: {err}"))
+            .ok()?;
+        let manager = globals.bind(&queue.handle(), 1..=1, ()).ok()?;
+
+        Some(Self {
+            connection,
+            queue,
+            manager,
+        })
+    }
+
+    /// Inhibits idle while the surface is visible, `None` if it isn't a Wayland surface
+    pub fn inhibit(&self, surface: &gdk::Surface) -> Option<WaylandInhibitor> {
+        let surface = surface.downcast_ref::<WaylandSurface>()?.wl_surface()?;
+        let inhibitor = self
+            .manager
+            .create_inhibitor(&surface, &self.queue.handle(), ());
+        flush(&self.connection);
+
+        Some(WaylandInhibitor {
+            connection: self.connection.clone(),
+            inhibitor,
+        })
+    }
+}
+
+impl Drop for IdleInhibitManager {
+    fn drop(&mut self) {
+        self.manager.destroy();
+        flush(&self.connection);
+    }
+}
+
+/// `zwp_idle_inhibitor_v1` on the bar surface
+#[derive(Debug)]
+pub struct WaylandInhibitor {
+    connection: Connection,
+    inhibitor: ZwpIdleInhibitorV1,
+}
+
+impl Drop for WaylandInhibitor {
+    fn drop(&mut self) {
+        self.inhibitor.destroy();
+        flush(&self.connection);
+    }
+}
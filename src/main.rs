@@ -21,23 +21,52 @@ use crate::{
     pulse_wrapper::PulseaudioEvent,
     widgets::{
         active_window::{ActiveWindowModel, ActiveWindowMsg},
-        battery::{BatteryModel, BatteryMsg},
-        cpu::{CpuModel, CpuMsg},
+        backlight::BacklightModel,
+        battery::{BatteryInit, BatteryModel, BatteryMsg},
+        bluetooth::BluetoothModel,
+        cpu::{CpuInit, CpuModel, CpuMsg},
+        custom::CustomModel,
+        disk::{DiskModel, DiskMsg},
+        idle_inhibitor::IdleInhibitorModel,
         image::ImageModel,
         layout::{LayoutInit, LayoutModel, LayoutMsg},
-        memory::{MemoryModel, MemoryMsg},
+        memory::{MemoryInit, MemoryModel, MemoryMsg, MemoryStats},
+        mpris::MprisModel,
         network::{NetworkModel, NetworkMsg},
+        notifications::NotificationsModel,
+        power_profiles::PowerProfilesModel,
         sound::{SoundModel, SoundMsg},
-        time::{TimeModel, TimeMsg},
-        workspaces::{WorkspacesModel, WorkspacesMsg},
+        submap::{SubmapModel, SubmapMsg},
+        temperature::{TemperatureModel, TemperatureMsg},
+        time::TimeModel,
+        tray::TrayModel,
+        workspaces::{WorkspacesInit, WorkspacesModel, WorkspacesMsg},
     },
 };
 
+mod backlight;
 mod battery;
+mod bluetooth;
 mod config;
+mod desktop_entry;
+mod hwmon;
 mod hyprland;
+mod ics;
+mod idle_inhibitor;
+mod meminfo;
+mod mpris;
+mod network_manager;
+mod notifications;
+mod power_profiles;
 mod pulse_wrapper;
+mod status_notifier;
+mod sysfs;
+#[cfg(test)]
+mod test_util;
+mod units;
+mod upower;
 mod widgets;
+mod zram;
 
 #[derive(Debug)]
 enum AppMsg {
@@ -52,14 +81,25 @@ struct AppModel {
 
     workspaces: Controller<WorkspacesModel>,
     active_window: Controller<ActiveWindowModel>,
+    submap: Controller<SubmapModel>,
 
     network: Controller<NetworkModel>,
     battery: Option<Controller<BatteryModel>>,
     cpu: Controller<CpuModel>,
+    temperature: Option<Controller<TemperatureModel>>,
     memory: Controller<MemoryModel>,
+    disk: Option<Controller<DiskModel>>,
+    _mpris: Option<Controller<MprisModel>>,
+    _tray: Option<Controller<TrayModel>>,
+    _notifications: Option<Controller<NotificationsModel>>,
+    _custom: Vec<Controller<CustomModel>>,
     sound: Controller<SoundModel>,
+    _backlight: Option<Controller<BacklightModel>>,
+    _bluetooth: Option<Controller<BluetoothModel>>,
+    _power_profiles: Option<Controller<PowerProfilesModel>>,
+    _idle_inhibitor: Option<Controller<IdleInhibitorModel>>,
     layout: Controller<LayoutModel>,
-    time: Controller<TimeModel>,
+    _time: Controller<TimeModel>,
 }
 
 #[relm4::component]
@@ -106,54 +146,159 @@ impl SimpleComponent for AppModel {
 
         let ctx = MainContext::default();
         let workspaces = {
-            let mut workspaces = ctx.block_on(hyprland::get_workspaces()).unwrap();
+            let mut workspaces = ctx
+                .block_on(hyprland::get_workspaces())
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to get workspaces: {err}");
+                    Vec::new()
+                });
             workspaces.sort_unstable_by_key(|workspace| workspace.id);
             workspaces
         };
 
-        let main_keyboard = ctx.block_on(hyprland::get_main_keyboard()).unwrap();
+        let keyboard = ctx
+            .block_on(hyprland::get_keyboard(config.layout_keyboard.as_deref()))
+            .or_else(|err| {
+                eprintln!("Failed to get the layout keyboard: {err}");
+                ctx.block_on(hyprland::get_keyboard(None))
+            })
+            .unwrap();
 
-        let workspaces = WorkspacesModel::builder().launch(workspaces).detach();
+        let clients = ctx.block_on(hyprland::get_clients()).unwrap_or_else(|err| {
+            eprintln!("Failed to get clients: {err}");
+            Vec::new()
+        });
+
+        let monitors = ctx
+            .block_on(hyprland::get_monitors())
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to get monitors: {err}");
+                Vec::new()
+            });
+
+        let workspaces = WorkspacesModel::builder()
+            .launch(WorkspacesInit {
+                workspaces,
+                clients,
+                monitors,
+                monitor: config.monitor.clone(),
+                config: config.workspaces.clone().unwrap_or_default(),
+            })
+            .detach();
         let workspaces_widget = workspaces.widget().clone();
 
-        let active_window = ActiveWindowModel::builder().launch(()).detach();
+        let active_window = ActiveWindowModel::builder()
+            .launch(config.active_window.unwrap_or_default())
+            .detach();
         let active_window_widget = active_window.widget().clone();
 
+        let submap = SubmapModel::builder()
+            .launch(config.submap_map.unwrap_or_default())
+            .detach();
+        let submap_widget = submap.widget().clone();
+
         let refresh_specifics = RefreshKind::nothing()
             .with_cpu(CpuRefreshKind::nothing().with_cpu_usage())
-            .with_memory(MemoryRefreshKind::nothing().with_ram());
+            .with_memory(MemoryRefreshKind::nothing().with_ram().with_swap());
         let system = Rc::new(RefCell::new(System::new_with_specifics(refresh_specifics)));
 
-        let network = NetworkModel::builder().launch(()).detach();
+        let network = NetworkModel::builder()
+            .launch(config.network.unwrap_or_default())
+            .detach();
         let network_widget = network.widget().clone();
 
-        let battery = if let Some(battery_name) = config.battery_name {
+        let battery = if config.battery_name.is_some() || config.battery.is_some() {
             let battery = BatteryModel::builder()
-                .launch(battery_name.clone())
+                .launch(BatteryInit {
+                    battery_name: config.battery_name,
+                    config: config.battery.unwrap_or_default(),
+                })
                 .detach();
             Some(battery)
         } else {
             None
         };
 
-        let cpu = CpuModel::builder().launch(()).detach();
+        let processes_count = config.processes_count.unwrap_or(10);
+
+        let cpu = CpuModel::builder()
+            .launch(CpuInit {
+                system: system.clone(),
+                processes_count,
+            })
+            .detach();
         let cpu_widget = cpu.widget().clone();
 
-        let memory = MemoryModel::builder().launch(()).detach();
+        let temperature = config
+            .temperature
+            .map(|temperature| TemperatureModel::builder().launch(temperature).detach());
+
+        let memory = MemoryModel::builder()
+            .launch(MemoryInit {
+                config: config.memory.unwrap_or_default(),
+                system: system.clone(),
+                processes_count,
+            })
+            .detach();
         let memory_widget = memory.widget().clone();
 
+        let disk = config
+            .disk
+            .map(|disk| DiskModel::builder().launch(disk).detach());
+
+        let mpris = config
+            .mpris
+            .map(|mpris| MprisModel::builder().launch(mpris).detach());
+
+        let tray = config
+            .tray
+            .map(|tray| TrayModel::builder().launch(tray).detach());
+
+        let notifications = config
+            .notifications
+            .map(|notifications| NotificationsModel::builder().launch(notifications).detach());
+
+        let custom = config
+            .custom
+            .unwrap_or_default()
+            .into_iter()
+            .map(|custom| CustomModel::builder().launch(custom).detach())
+            .collect::<Vec<_>>();
+
         let sound = SoundModel::builder().launch(()).detach();
         let sound_widget = sound.widget().clone();
 
+        let backlight = config
+            .backlight
+            .map(|backlight| BacklightModel::builder().launch(backlight).detach());
+
+        let bluetooth = config
+            .bluetooth
+            .map(|bluetooth| BluetoothModel::builder().launch(bluetooth).detach());
+
+        let power_profiles = config.power_profiles.map(|power_profiles| {
+            PowerProfilesModel::builder()
+                .launch(power_profiles)
+                .detach()
+        });
+
+        let idle_inhibitor = config.idle_inhibitor.map(|idle_inhibitor| {
+            IdleInhibitorModel::builder()
+                .launch(idle_inhibitor)
+                .detach()
+        });
+
         let layout = LayoutModel::builder()
             .launch(LayoutInit {
-                active_layout: main_keyboard.active_keymap,
+                keyboard,
                 layout_map: config.layout_map.unwrap_or_default().clone(),
             })
             .detach();
         let layout_widget = layout.widget().clone();
 
-        let time = TimeModel::builder().launch(()).detach();
+        let time = TimeModel::builder()
+            .launch(config.time.unwrap_or_default())
+            .detach();
         let time_widget = time.widget().clone();
 
         view! {
@@ -162,6 +307,7 @@ impl SimpleComponent for AppModel {
 
                 // append: &image_widget,
                 append: &workspaces_widget,
+                append: &submap_widget,
             },
             end_widget = gtk::Box {
                 set_spacing: 4,
@@ -184,23 +330,91 @@ impl SimpleComponent for AppModel {
             end_widget.insert_child_after(&battery.widget().clone(), Some(&network_widget));
         }
 
+        if let Some(ref temperature) = temperature {
+            end_widget.insert_child_after(&temperature.widget().clone(), Some(&cpu_widget));
+        }
+
+        if let Some(ref disk) = disk {
+            end_widget.insert_child_after(&disk.widget().clone(), Some(&memory_widget));
+        }
+
+        // Inserted after the sound widget in reverse order
+        if let Some(ref idle_inhibitor) = idle_inhibitor {
+            end_widget.insert_child_after(&idle_inhibitor.widget().clone(), Some(&sound_widget));
+        }
+
+        if let Some(ref power_profiles) = power_profiles {
+            end_widget.insert_child_after(&power_profiles.widget().clone(), Some(&sound_widget));
+        }
+
+        if let Some(ref bluetooth) = bluetooth {
+            end_widget.insert_child_after(&bluetooth.widget().clone(), Some(&sound_widget));
+        }
+
+        if let Some(ref backlight) = backlight {
+            end_widget.insert_child_after(&backlight.widget().clone(), Some(&sound_widget));
+        }
+
+        for custom in custom.iter().rev() {
+            end_widget.prepend(&custom.widget().clone());
+        }
+
+        if let Some(ref mpris) = mpris {
+            end_widget.prepend(&mpris.widget().clone());
+        }
+
+        if let Some(ref tray) = tray {
+            end_widget.append(&tray.widget().clone());
+        }
+
+        if let Some(ref notifications) = notifications {
+            end_widget.append(&notifications.widget().clone());
+        }
+
         let model = AppModel {
             system: system.clone(),
 
             workspaces,
             active_window,
+            submap,
 
             network,
             battery,
             cpu,
+            temperature,
             memory,
+            disk,
+            _mpris: mpris,
+            _tray: tray,
+            _notifications: notifications,
+            _custom: custom,
             sound,
+            _backlight: backlight,
+            _bluetooth: bluetooth,
+            _power_profiles: power_profiles,
+            _idle_inhibitor: idle_inhibitor,
             layout,
-            time,
+            _time: time,
         };
 
         let widgets = view_output!();
 
+        if let Some(monitor_name) = &config.monitor {
+            let monitors = Display::default().unwrap().monitors();
+            let monitor = (0..monitors.n_items())
+                .filter_map(|idx| monitors.item(idx).and_downcast::<gtk::gdk::Monitor>())
+                .find(|monitor| {
+                    monitor
+                        .connector()
+                        .is_some_and(|name| name == *monitor_name)
+                });
+
+            match monitor {
+                Some(monitor) => root.set_monitor(Some(&monitor)),
+                None => eprintln!("Monitor {monitor_name} not found"),
+            }
+        }
+
         if let Some(margins) = &config.margins {
             if let Some(margin) = margins.left {
                 root.set_margin(Edge::Left, margin);
@@ -293,13 +507,19 @@ impl SimpleComponent for AppModel {
 
     fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
         match msg {
-            AppMsg::HyprlandEvent(HyprlandEvent::ActiveLayout { layout, .. }) => {
-                self.layout.emit(LayoutMsg::ActiveLayout { layout });
+            AppMsg::HyprlandEvent(HyprlandEvent::ActiveLayout { name, layout }) => {
+                self.layout.emit(LayoutMsg::ActiveLayout {
+                    keyboard: name,
+                    layout,
+                });
             }
             AppMsg::HyprlandEvent(HyprlandEvent::ActiveWindow { class, title }) => {
                 self.active_window
                     .emit(ActiveWindowMsg::ActiveWindow { class, title });
             }
+            AppMsg::HyprlandEvent(HyprlandEvent::Submap { name }) => {
+                self.submap.emit(SubmapMsg::Submap { name });
+            }
             AppMsg::HyprlandEvent(HyprlandEvent::WorkspaceV2 { id, .. }) => {
                 self.workspaces.emit(WorkspacesMsg::Activate { id });
             }
@@ -309,6 +529,48 @@ impl SimpleComponent for AppModel {
             AppMsg::HyprlandEvent(HyprlandEvent::DestroyWorkspaceV2 { id, .. }) => {
                 self.workspaces.emit(WorkspacesMsg::Destroy { id });
             }
+            AppMsg::HyprlandEvent(HyprlandEvent::ActiveSpecial { name, monitor }) => {
+                self.workspaces
+                    .emit(WorkspacesMsg::ActivateSpecial { name, monitor });
+            }
+            AppMsg::HyprlandEvent(HyprlandEvent::FocusedMon {
+                monitor,
+                workspace_name,
+            }) => {
+                self.workspaces.emit(WorkspacesMsg::FocusMonitor {
+                    monitor,
+                    workspace_name,
+                });
+            }
+            AppMsg::HyprlandEvent(HyprlandEvent::MoveWorkspaceV2 { id, monitor }) => {
+                self.workspaces.emit(WorkspacesMsg::Move { id, monitor });
+            }
+            AppMsg::HyprlandEvent(HyprlandEvent::OpenWindow {
+                address,
+                workspace_name,
+                class,
+            }) => {
+                self.workspaces.emit(WorkspacesMsg::OpenWindow {
+                    address,
+                    workspace_name,
+                    class,
+                });
+            }
+            AppMsg::HyprlandEvent(HyprlandEvent::CloseWindow { address }) => {
+                self.workspaces.emit(WorkspacesMsg::CloseWindow { address });
+            }
+            AppMsg::HyprlandEvent(HyprlandEvent::Urgent { address }) => {
+                self.workspaces.emit(WorkspacesMsg::Urgent { address });
+            }
+            AppMsg::HyprlandEvent(HyprlandEvent::MoveWindowV2 {
+                address,
+                workspace_id,
+            }) => {
+                self.workspaces.emit(WorkspacesMsg::MoveWindow {
+                    address,
+                    workspace_id,
+                });
+            }
             AppMsg::NetworkRefresh {
                 transmitted,
                 received,
@@ -329,12 +591,21 @@ impl SimpleComponent for AppModel {
                     usage: system.global_cpu_usage(),
                 });
 
-                self.memory.emit(MemoryMsg::UpdateStats {
-                    used: system.used_memory(),
+                if let Some(ref temperature) = self.temperature {
+                    temperature.emit(TemperatureMsg::Update);
+                }
+
+                self.memory.emit(MemoryMsg::UpdateStats(MemoryStats {
                     total: system.total_memory(),
-                });
+                    used: system.used_memory(),
+                    available: system.available_memory(),
+                    total_swap: system.total_swap(),
+                    used_swap: system.used_swap(),
+                }));
 
-                self.time.emit(TimeMsg::Update);
+                if let Some(ref disk) = self.disk {
+                    disk.emit(DiskMsg::Update);
+                }
             }
             AppMsg::PulseaudioEvent(event) => self.sound.emit(SoundMsg::Update(event)),
             _ => {}
@@ -345,22 +616,43 @@ impl SimpleComponent for AppModel {
 fn main() -> anyhow::Result<()> {
     gtk::init()?;
 
-    let config_path = env::var("XDG_CONFIG_HOME").map_or_else(
-        |_| {
-            let mut home = PathBuf::from(env::var("HOME").expect("HOME is not set"));
-            home.push(".config");
-            home
-        },
-        |x| PathBuf::from(&x),
-    );
+    let mut config_file = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "--config" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("{arg} requires a path"))?;
+                config_file = Some(env::current_dir()?.join(path));
+            }
+            _ => anyhow::bail!("Unknown argument {arg}\nUsage: crabbar [--config <path>]"),
+        }
+    }
+
+    let config_file = config_file.unwrap_or_else(|| {
+        let config_path = env::var("XDG_CONFIG_HOME").map_or_else(
+            |_| {
+                let mut home = PathBuf::from(env::var("HOME").expect("HOME is not set"));
+                home.push(".config");
+                home
+            },
+            |x| PathBuf::from(&x),
+        );
+
+        config_path.join("crabbar").join("config.json")
+    });
 
-    let config_path = config_path.join("crabbar");
+    // The stylesheet and relative paths in the config are next to the config file
+    let config_path = config_file
+        .parent()
+        .expect("Config file has no parent directory")
+        .to_path_buf();
 
     env::set_current_dir(&config_path).expect("Failed to set current directory");
 
     let config = {
-        let config_path = config_path.join("config.json");
-        let data = fs::read_to_string(config_path).expect("Failed to read config");
+        let data = fs::read_to_string(&config_file).expect("Failed to read config");
         serde_json::from_str::<Config>(&data).expect("Failed to parse config")
     };
 
@@ -372,8 +664,10 @@ fn main() -> anyhow::Result<()> {
     };
     gtk::style_context_add_provider_for_display(&Display::default().unwrap(), &provider, 3000);
 
-    let relm = RelmApp::new("com.github.underengineering.Crabbar");
-    relm.allow_multiple_instances(false);
+    // The arguments are parsed above, GTK would reject them
+    let relm = RelmApp::new("com.github.underengineering.Crabbar").with_args(Vec::new());
+    // One bar per config, e.g. for every monitor
+    relm.allow_multiple_instances(true);
     relm.run::<AppModel>(config);
 
     Ok(())
@@ -0,0 +1,58 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+// https://www.kernel.org/doc/html/latest/filesystems/proc.html#meminfo
+/// Memory used by the page cache and buffers, in bytes. sysinfo already reads the other fields.
+pub fn get_cached_memory() -> io::Result<u64> {
+    parse_cached(BufReader::new(File::open("/proc/meminfo")?))
+}
+
+/// Stops at `Cached`, which comes right after `Buffers` near the top
+fn parse_cached(meminfo: impl BufRead) -> io::Result<u64> {
+    let mut cached = 0;
+
+    for line in meminfo.lines() {
+        let line = line?;
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if key != "Cached" && key != "Buffers" {
+            continue;
+        }
+
+        let kib = value
+            .trim()
+            .strip_suffix("kB")
+            .and_then(|value| value.trim_end().parse::<u64>().ok())
+            .unwrap_or_default();
+        cached += kib * 1024;
+
+        if key == "Cached" {
+            break;
+        }
+    }
+
+    Ok(cached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached() {
+        let meminfo = "MemTotal:       32768000 kB\n\
+                       MemFree:         1024000 kB\n\
+                       MemAvailable:   16384000 kB\n\
+                       Buffers:            2048 kB\n\
+                       Cached:          8192000 kB\n\
+                       SwapCached:         4096 kB\n";
+        assert_eq!(
+            parse_cached(meminfo.as_bytes()).unwrap(),
+            (8192000 + 2048) * 1024
+        );
+        assert_eq!(parse_cached("".as_bytes()).unwrap(), 0);
+    }
+}
@@ -0,0 +1,285 @@
+use relm4::gtk::{
+    gio::{self, prelude::*, DBusCallFlags, DBusConnection, DBusProxy, DBusProxyFlags},
+    glib::{self, Variant, VariantDict},
+};
+
+// https://specifications.freedesktop.org/mpris-spec/latest/
+pub const PLAYER_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerState {
+    pub status: PlaybackStatus,
+    pub artist: String,
+    pub title: String,
+}
+
+/// Reads the artists and the title from the `Metadata` property
+fn parse_metadata(metadata: &Variant) -> (String, String) {
+    let metadata = VariantDict::new(Some(metadata));
+
+    let artist = metadata
+        .lookup_value("xesam:artist", None)
+        .and_then(|artist| {
+            // Some players send a single string instead of a list
+            artist
+                .get::<Vec<String>>()
+                .map(|artists| artists.join(", "))
+                .or_else(|| artist.get::<String>())
+        })
+        .unwrap_or_default();
+
+    let title = metadata
+        .lookup_value("xesam:title", None)
+        .and_then(|title| title.get::<String>())
+        .unwrap_or_default();
+
+    (artist, title)
+}
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    /// Well-known bus name, e.g. `org.mpris.MediaPlayer2.spotify`
+    pub bus_name: String,
+    proxy: DBusProxy,
+}
+
+impl Player {
+    pub async fn new(connection: &DBusConnection, bus_name: String) -> Result<Self, glib::Error> {
+        let proxy = DBusProxy::new_future(
+            connection,
+            DBusProxyFlags::NONE,
+            None,
+            Some(&bus_name),
+            OBJECT_PATH,
+            PLAYER_INTERFACE,
+        )
+        .await?;
+
+        Ok(Self { bus_name, proxy })
+    }
+
+    /// Short name of the player, without the prefix and the instance suffix
+    pub fn name(&self) -> &str {
+        let name = self
+            .bus_name
+            .strip_prefix(PLAYER_PREFIX)
+            .unwrap_or(&self.bus_name);
+        name.split_once(".instance").map_or(name, |(name, _)| name)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.proxy.name_owner().is_some()
+    }
+
+    pub fn state(&self) -> PlayerState {
+        let status = match self
+            .proxy
+            .cached_property("PlaybackStatus")
+            .and_then(|status| status.get::<String>())
+            .as_deref()
+        {
+            Some("Playing") => PlaybackStatus::Playing,
+            Some("Paused") => PlaybackStatus::Paused,
+            _ => PlaybackStatus::Stopped,
+        };
+
+        let (artist, title) = self
+            .proxy
+            .cached_property("Metadata")
+            .map(|metadata| parse_metadata(&metadata))
+            .unwrap_or_default();
+
+        PlayerState {
+            status,
+            artist,
+            title,
+        }
+    }
+
+    /// Calls `f` after the cached properties were updated
+    pub fn connect_changed<F: Fn() + 'static>(&self, f: F) {
+        self.proxy
+            .connect_local("g-properties-changed", false, move |_| {
+                f();
+                None
+            });
+    }
+
+    pub async fn call(&self, method: &str) -> Result<(), glib::Error> {
+        self.proxy
+            .call_future(method, None, DBusCallFlags::NONE, -1)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Returns the bus names of all players
+pub async fn list_players(connection: &DBusConnection) -> Result<Vec<String>, glib::Error> {
+    let names = connection
+        .call_future(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "ListNames",
+            None,
+            None,
+            DBusCallFlags::NONE,
+            -1,
+        )
+        .await?;
+
+    let (names,) = names.get::<(Vec<String>,)>().unwrap_or_default();
+    Ok(names
+        .into_iter()
+        .filter(|name| name.starts_with(PLAYER_PREFIX))
+        .collect())
+}
+
+/// Calls `f` with the bus name and whether the player appeared or vanished
+pub fn watch_players<F: Fn(String, bool) + 'static>(connection: &DBusConnection, f: F) {
+    connection.signal_subscribe(
+        Some("org.freedesktop.DBus"),
+        Some("org.freedesktop.DBus"),
+        Some("NameOwnerChanged"),
+        Some("/org/freedesktop/DBus"),
+        // Matches all names in the namespace, the prefix without the trailing dot
+        Some(PLAYER_PREFIX.trim_end_matches('.')),
+        gio::DBusSignalFlags::MATCH_ARG0_NAMESPACE,
+        move |_, _, _, _, _, parameters| {
+            let Some((name, _, new_owner)) = parameters.get::<(String, String, String)>() else {
+                return;
+            };
+
+            f(name, !new_owner.is_empty());
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{connect, wait_for, with_test_bus};
+    use relm4::gtk::gio::DBusNodeInfo;
+    use std::{cell::RefCell, rc::Rc};
+
+    const PLAYER_XML: &str = r#"
+        <node>
+          <interface name="org.mpris.MediaPlayer2.Player">
+            <property name="PlaybackStatus" type="s" access="read"/>
+            <property name="Metadata" type="a{sv}" access="read"/>
+          </interface>
+        </node>
+    "#;
+
+    /// Exports a playing player on the connection and requests `name` for it
+    async fn export_player(connection: &DBusConnection, name: &str) {
+        let node_info = DBusNodeInfo::for_xml(PLAYER_XML).unwrap();
+        let interface_info = node_info.lookup_interface(PLAYER_INTERFACE).unwrap();
+        connection
+            .register_object(OBJECT_PATH, &interface_info)
+            .property(|_, _, _, _, property| match property {
+                "PlaybackStatus" => "Playing".to_variant(),
+                _ => {
+                    let metadata = VariantDict::new(None);
+                    metadata.insert_value("xesam:title", &"Song".to_variant());
+                    metadata.insert_value("xesam:artist", &vec!["Artist"].to_variant());
+                    metadata.end()
+                }
+            })
+            .build()
+            .unwrap();
+
+        connection
+            .call_future(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                "org.freedesktop.DBus",
+                "RequestName",
+                Some(&(name, 0u32).to_variant()),
+                None,
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn player_on_test_bus() {
+        with_test_bus(|address| async move {
+            let name = format!("{PLAYER_PREFIX}test.instance42");
+            let connection = connect(&address).await;
+
+            let events = Rc::new(RefCell::new(Vec::new()));
+            watch_players(&connection, {
+                let events = events.clone();
+                move |name, running| events.borrow_mut().push((name, running))
+            });
+            // The unique name of the new connection is outside of the namespace
+            connect(&address).await;
+
+            let player_connection = connect(&address).await;
+            export_player(&player_connection, &name).await;
+
+            assert_eq!(list_players(&connection).await.unwrap(), vec![name.clone()]);
+            wait_for(|| !events.borrow().is_empty()).await;
+            assert_eq!(*events.borrow(), [(name.clone(), true)]);
+
+            let player = Player::new(&connection, name.clone()).await.unwrap();
+            assert_eq!(player.name(), "test");
+            assert!(player.is_running());
+            assert_eq!(
+                player.state(),
+                PlayerState {
+                    status: PlaybackStatus::Playing,
+                    artist: "Artist".to_string(),
+                    title: "Song".to_string(),
+                }
+            );
+
+            player_connection.close_future().await.unwrap();
+            wait_for(|| events.borrow().len() == 2).await;
+            assert_eq!(events.borrow()[1], (name.clone(), false));
+            assert!(list_players(&connection).await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn metadata_with_multiple_artists() {
+        let metadata = VariantDict::new(None);
+        metadata.insert_value("xesam:title", &"Song".to_variant());
+        metadata.insert_value("xesam:artist", &vec!["First", "Second"].to_variant());
+        metadata.insert_value("mpris:length", &180_000_000i64.to_variant());
+
+        assert_eq!(
+            parse_metadata(&metadata.end()),
+            ("First, Second".to_string(), "Song".to_string())
+        );
+    }
+
+    #[test]
+    fn metadata_missing_fields_and_single_artist() {
+        let metadata = VariantDict::new(None);
+        assert_eq!(
+            parse_metadata(&metadata.end()),
+            (String::new(), String::new())
+        );
+
+        metadata.insert_value("xesam:artist", &"Artist".to_variant());
+        assert_eq!(
+            parse_metadata(&metadata.end()),
+            ("Artist".to_string(), String::new())
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use futures_util::future::join_all;
+use relm4::gtk::{
+    gio::{prelude::*, DBusCallFlags, DBusConnection, DBusSignalFlags},
+    glib::{self, variant::ObjectPath, Variant, VariantDict},
+};
+use std::{collections::HashMap, rc::Rc};
+
+// https://networkmanager.dev/docs/api/latest/spec.html
+const NM_NAME: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const NM_INTERFACE: &str = "org.freedesktop.NetworkManager";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const SETTINGS_CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+const ACTIVE_INTERFACE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const DEVICE_INTERFACE: &str = "org.freedesktop.NetworkManager.Device";
+const WIRELESS_INTERFACE: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const ACCESS_POINT_INTERFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+const DEVICE_TYPE_WIFI: u32 = 2;
+pub const TYPE_WIRELESS: &str = "802-11-wireless";
+pub const TYPE_ETHERNET: &str = "802-3-ethernet";
+const VPN_TYPES: [&str; 2] = ["vpn", "wireguard"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    #[default]
+    Unknown,
+    None,
+    /// Behind a captive portal
+    Portal,
+    /// Connected, but without access to the internet
+    Limited,
+    Full,
+}
+
+impl From<u32> for Connectivity {
+    fn from(connectivity: u32) -> Self {
+        match connectivity {
+            1 => Self::None,
+            2 => Self::Portal,
+            3 => Self::Limited,
+            4 => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveConnection {
+    pub path: String,
+    /// Path of the saved connection it was activated from
+    pub connection: String,
+    pub id: String,
+    /// Connection type, e.g. `802-11-wireless`
+    pub kind: String,
+    pub vpn: bool,
+    /// Signal strength in percent of Wi-Fi connections
+    pub strength: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkStatus {
+    pub connectivity: Connectivity,
+    /// The connection with the default route first
+    pub active: Vec<ActiveConnection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPoint {
+    pub ssid: String,
+    pub strength: u8,
+    pub secured: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedConnection {
+    pub path: String,
+    pub id: String,
+    pub kind: String,
+    pub ssid: Option<String>,
+}
+
+impl SavedConnection {
+    /// Reads the connection from the `a{sa{sv}}` returned by `GetSettings`
+    fn from_settings(path: String, settings: &Variant) -> Option<Self> {
+        let settings = settings
+            .iter()
+            .filter_map(|setting| {
+                let name = setting.child_value(0).str()?.to_string();
+                Some((name, VariantDict::new(Some(&setting.child_value(1)))))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let connection = settings.get("connection")?;
+        let ssid = settings
+            .get(TYPE_WIRELESS)
+            .and_then(|wireless| wireless.lookup::<Vec<u8>>("ssid").ok().flatten())
+            .map(|ssid| String::from_utf8_lossy(&ssid).to_string());
+
+        Some(Self {
+            path,
+            id: connection.lookup::<String>("id").ok().flatten()?,
+            kind: connection.lookup::<String>("type").ok().flatten()?,
+            ssid,
+        })
+    }
+
+    pub fn is_vpn(&self) -> bool {
+        VPN_TYPES.contains(&self.kind.as_str())
+    }
+}
+
+/// Keeps the strongest access point of every network, strongest first
+fn dedup_access_points(mut access_points: Vec<AccessPoint>) -> Vec<AccessPoint> {
+    access_points.retain(|access_point| !access_point.ssid.is_empty());
+    access_points.sort_by_key(|access_point| std::cmp::Reverse(access_point.strength));
+
+    let mut seen = Vec::new();
+    access_points.retain(|access_point| {
+        if seen.contains(&access_point.ssid) {
+            false
+        } else {
+            seen.push(access_point.ssid.clone());
+            true
+        }
+    });
+
+    access_points
+}
+
+fn object_path(path: &str) -> Result<ObjectPath, glib::Error> {
+    ObjectPath::try_from(path)
+        .map_err(|err| glib::Error::new(glib::FileError::Inval, &err.to_string()))
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkManager {
+    connection: DBusConnection,
+}
+
+impl NetworkManager {
+    pub fn new(connection: DBusConnection) -> Self {
+        Self { connection }
+    }
+
+    async fn call(
+        &self,
+        path: &str,
+        interface: &str,
+        method: &str,
+        parameters: Option<&Variant>,
+    ) -> Result<Variant, glib::Error> {
+        self.connection
+            .call_future(
+                Some(NM_NAME),
+                path,
+                interface,
+                method,
+                parameters,
+                None,
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await
+    }
+
+    async fn properties(&self, path: &str, interface: &str) -> Result<VariantDict, glib::Error> {
+        let properties = self
+            .call(
+                path,
+                PROPERTIES_INTERFACE,
+                "GetAll",
+                Some(&(interface,).to_variant()),
+            )
+            .await?;
+
+        Ok(VariantDict::new(Some(&properties.child_value(0))))
+    }
+
+    async fn active_connection(&self, path: String) -> Result<ActiveConnection, glib::Error> {
+        let properties = self.properties(&path, ACTIVE_INTERFACE).await?;
+        let kind = properties
+            .lookup::<String>("Type")
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+
+        let access_point = properties
+            .lookup::<ObjectPath>("SpecificObject")
+            .ok()
+            .flatten()
+            .filter(|access_point| kind == TYPE_WIRELESS && access_point.as_str() != "/");
+        let strength = match access_point {
+            Some(access_point) => self
+                .properties(&access_point, ACCESS_POINT_INTERFACE)
+                .await?
+                .lookup::<u8>("Strength")
+                .ok()
+                .flatten(),
+            None => None,
+        };
+
+        Ok(ActiveConnection {
+            connection: properties
+                .lookup::<ObjectPath>("Connection")
+                .ok()
+                .flatten()
+                .map(|connection| connection.to_string())
+                .unwrap_or_default(),
+            id: properties
+                .lookup::<String>("Id")
+                .ok()
+                .flatten()
+                .unwrap_or_default(),
+            vpn: VPN_TYPES.contains(&kind.as_str())
+                || properties
+                    .lookup::<bool>("Vpn")
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default(),
+            kind,
+            strength,
+            path,
+        })
+    }
+
+    pub async fn status(&self) -> Result<NetworkStatus, glib::Error> {
+        let properties = self.properties(NM_PATH, NM_INTERFACE).await?;
+
+        let primary = properties
+            .lookup::<ObjectPath>("PrimaryConnection")
+            .ok()
+            .flatten();
+        let mut paths = properties
+            .lookup::<Vec<ObjectPath>>("ActiveConnections")
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        paths.sort_by_key(|path| Some(path) != primary.as_ref());
+
+        // Connections can be deactivated in the meantime
+        let active = join_all(
+            paths
+                .into_iter()
+                .map(|path| self.active_connection(path.to_string())),
+        )
+        .await
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+
+        Ok(NetworkStatus {
+            connectivity: properties
+                .lookup::<u32>("Connectivity")
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+                .into(),
+            active,
+        })
+    }
+
+    async fn wireless_devices(&self) -> Result<Vec<String>, glib::Error> {
+        let (devices,) = self
+            .call(NM_PATH, NM_INTERFACE, "GetDevices", None)
+            .await?
+            .get::<(Vec<ObjectPath>,)>()
+            .unwrap_or_default();
+
+        let mut wireless = Vec::new();
+        for device in devices {
+            let device_type = self
+                .properties(&device, DEVICE_INTERFACE)
+                .await?
+                .lookup::<u32>("DeviceType")
+                .ok()
+                .flatten();
+            if device_type == Some(DEVICE_TYPE_WIFI) {
+                wireless.push(device.to_string());
+            }
+        }
+
+        Ok(wireless)
+    }
+
+    async fn access_point(&self, path: &str) -> Result<AccessPoint, glib::Error> {
+        let properties = self.properties(path, ACCESS_POINT_INTERFACE).await?;
+        let flags = |key| {
+            properties
+                .lookup::<u32>(key)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+
+        Ok(AccessPoint {
+            ssid: properties
+                .lookup::<Vec<u8>>("Ssid")
+                .ok()
+                .flatten()
+                .map(|ssid| String::from_utf8_lossy(&ssid).to_string())
+                .unwrap_or_default(),
+            strength: properties
+                .lookup::<u8>("Strength")
+                .ok()
+                .flatten()
+                .unwrap_or_default(),
+            secured: flags("Flags") != 0 || flags("WpaFlags") != 0 || flags("RsnFlags") != 0,
+        })
+    }
+
+    /// Visible Wi-Fi networks, strongest first
+    pub async fn access_points(&self) -> Result<Vec<AccessPoint>, glib::Error> {
+        let mut access_points = Vec::new();
+        for device in self.wireless_devices().await? {
+            let (paths,) = self
+                .call(&device, WIRELESS_INTERFACE, "GetAllAccessPoints", None)
+                .await?
+                .get::<(Vec<ObjectPath>,)>()
+                .unwrap_or_default();
+
+            // Access points can disappear in the meantime, e.g. while scanning
+            let found = join_all(paths.iter().map(|path| self.access_point(path))).await;
+            access_points.extend(found.into_iter().filter_map(Result::ok));
+        }
+
+        Ok(dedup_access_points(access_points))
+    }
+
+    pub async fn saved_connections(&self) -> Result<Vec<SavedConnection>, glib::Error> {
+        let (paths,) = self
+            .call(SETTINGS_PATH, SETTINGS_INTERFACE, "ListConnections", None)
+            .await?
+            .get::<(Vec<ObjectPath>,)>()
+            .unwrap_or_default();
+
+        let mut connections = Vec::with_capacity(paths.len());
+        for path in paths {
+            let settings = self
+                .call(&path, SETTINGS_CONNECTION_INTERFACE, "GetSettings", None)
+                .await?;
+            connections.extend(SavedConnection::from_settings(
+                path.to_string(),
+                &settings.child_value(0),
+            ));
+        }
+
+        Ok(connections)
+    }
+
+    /// Asks the Wi-Fi devices to look for networks, `AccessPointAdded` is emitted for new ones
+    pub async fn request_scan(&self) -> Result<(), glib::Error> {
+        for device in self.wireless_devices().await? {
+            let options = VariantDict::new(None).end();
+            self.call(
+                &device,
+                WIRELESS_INTERFACE,
+                "RequestScan",
+                Some(&Variant::tuple_from_iter([options])),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Activates a saved connection, NetworkManager picks the device
+    pub async fn activate(&self, connection: &str) -> Result<(), glib::Error> {
+        let parameters = (
+            object_path(connection)?,
+            object_path("/")?,
+            object_path("/")?,
+        );
+        self.call(
+            NM_PATH,
+            NM_INTERFACE,
+            "ActivateConnection",
+            Some(&parameters.to_variant()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn deactivate(&self, active_connection: &str) -> Result<(), glib::Error> {
+        self.call(
+            NM_PATH,
+            NM_INTERFACE,
+            "DeactivateConnection",
+            Some(&(object_path(active_connection)?,).to_variant()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Calls `f` when the state, active connections or devices change, or access points appear
+    /// or disappear. Changes of the access points themselves, like their strength, are ignored
+    /// since they're sent all the time.
+    pub fn connect_changed<F: Fn() + 'static>(&self, f: F) {
+        let f = Rc::new(f);
+
+        // The first argument of `PropertiesChanged` is the interface
+        for interface in [NM_INTERFACE, ACTIVE_INTERFACE, DEVICE_INTERFACE] {
+            let f = f.clone();
+            self.connection.signal_subscribe(
+                Some(NM_NAME),
+                Some(PROPERTIES_INTERFACE),
+                Some("PropertiesChanged"),
+                None,
+                Some(interface),
+                DBusSignalFlags::NONE,
+                move |_, _, _, _, _, _| f(),
+            );
+        }
+
+        for signal in ["AccessPointAdded", "AccessPointRemoved"] {
+            let f = f.clone();
+            self.connection.signal_subscribe(
+                Some(NM_NAME),
+                Some(WIRELESS_INTERFACE),
+                Some(signal),
+                None,
+                None,
+                DBusSignalFlags::NONE,
+                move |_, _, _, _, _, _| f(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access_point(ssid: &str, strength: u8) -> AccessPoint {
+        AccessPoint {
+            ssid: ssid.to_string(),
+            strength,
+            secured: true,
+        }
+    }
+
+    #[test]
+    fn strongest_access_points() {
+        assert_eq!(
+            dedup_access_points(vec![
+                access_point("home", 40),
+                access_point("", 90),
+                access_point("cafe", 60),
+                access_point("home", 70),
+            ]),
+            vec![access_point("home", 70), access_point("cafe", 60)]
+        );
+    }
+
+    #[test]
+    fn wireless_settings() {
+        let connection = VariantDict::new(None);
+        connection.insert_value("id", &"Home".to_variant());
+        connection.insert_value("type", &TYPE_WIRELESS.to_variant());
+        let wireless = VariantDict::new(None);
+        wireless.insert_value("ssid", &b"home-5g".to_vec().to_variant());
+
+        let settings = Variant::array_from_iter_with_type(
+            glib::VariantTy::new("{sa{sv}}").unwrap(),
+            [
+                Variant::from_dict_entry(&"connection".to_variant(), &connection.end()),
+                Variant::from_dict_entry(&TYPE_WIRELESS.to_variant(), &wireless.end()),
+            ],
+        );
+
+        let saved = SavedConnection::from_settings("/1".to_string(), &settings).unwrap();
+        assert_eq!(saved.id, "Home");
+        assert_eq!(saved.ssid.as_deref(), Some("home-5g"));
+        assert!(!saved.is_vpn());
+    }
+}
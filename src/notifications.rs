@@ -0,0 +1,374 @@
+use chrono::{DateTime, Local};
+use relm4::gtk::{
+    gio::{self, prelude::*, BusNameOwnerFlags, DBusConnection, DBusNodeInfo},
+    glib::{self, Variant, VariantDict},
+};
+use std::{cell::Cell, rc::Rc};
+
+// https://specifications.freedesktop.org/notification-spec/latest/
+const NAME: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="org.freedesktop.Notifications">
+    <method name="Notify">
+      <arg name="app_name" type="s" direction="in"/>
+      <arg name="replaces_id" type="u" direction="in"/>
+      <arg name="app_icon" type="s" direction="in"/>
+      <arg name="summary" type="s" direction="in"/>
+      <arg name="body" type="s" direction="in"/>
+      <arg name="actions" type="as" direction="in"/>
+      <arg name="hints" type="a{sv}" direction="in"/>
+      <arg name="expire_timeout" type="i" direction="in"/>
+      <arg name="id" type="u" direction="out"/>
+    </method>
+    <method name="CloseNotification">
+      <arg name="id" type="u" direction="in"/>
+    </method>
+    <method name="GetCapabilities">
+      <arg name="capabilities" type="as" direction="out"/>
+    </method>
+    <method name="GetServerInformation">
+      <arg name="name" type="s" direction="out"/>
+      <arg name="vendor" type="s" direction="out"/>
+      <arg name="version" type="s" direction="out"/>
+      <arg name="spec_version" type="s" direction="out"/>
+    </method>
+    <signal name="NotificationClosed">
+      <arg name="id" type="u"/>
+      <arg name="reason" type="u"/>
+    </signal>
+    <signal name="ActionInvoked">
+      <arg name="id" type="u"/>
+      <arg name="action_key" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    Closed = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    pub key: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub id: u32,
+    /// Id of the notification this one replaces, if any
+    pub replaces_id: u32,
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub actions: Vec<Action>,
+    pub urgency: Urgency,
+    /// Milliseconds, -1 leaves it to the server and 0 never expires
+    pub expire_timeout: i32,
+    pub time: DateTime<Local>,
+}
+
+impl Notification {
+    /// Parses the parameters of `Notify`, the id is assigned by the caller
+    fn from_parameters(parameters: &Variant) -> Option<Self> {
+        let (app_name, replaces_id, app_icon, summary, body, actions, hints, expire_timeout) =
+            parameters.get::<(
+                String,
+                u32,
+                String,
+                String,
+                String,
+                Vec<String>,
+                VariantDict,
+                i32,
+            )>()?;
+
+        // Alternating keys and labels
+        let actions = actions
+            .chunks_exact(2)
+            .map(|action| Action {
+                key: action[0].clone(),
+                label: action[1].clone(),
+            })
+            .collect();
+
+        let urgency = match hints.lookup::<u8>("urgency").ok().flatten() {
+            Some(0) => Urgency::Low,
+            Some(2) => Urgency::Critical,
+            _ => Urgency::Normal,
+        };
+
+        // Some clients only send an image path in the hints
+        let app_icon = if app_icon.is_empty() {
+            hints
+                .lookup::<String>("image-path")
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        } else {
+            app_icon
+        };
+
+        Some(Self {
+            id: replaces_id,
+            replaces_id,
+            app_name,
+            app_icon,
+            summary,
+            body,
+            actions,
+            urgency,
+            expire_timeout,
+            time: Local::now(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum ServerEvent {
+    Notify(Notification),
+    /// Closed by the client with `CloseNotification`
+    Close(u32),
+}
+
+/// Exported `org.freedesktop.Notifications` object, used to signal closed notifications and
+/// invoked actions back to clients
+#[derive(Debug, Clone)]
+pub struct NotificationServer {
+    connection: DBusConnection,
+}
+
+impl NotificationServer {
+    /// Exports the server and requests the name, failing that is only logged since another
+    /// daemon may be running
+    pub fn start<F: Fn(ServerEvent) + 'static>(
+        connection: &DBusConnection,
+        f: F,
+    ) -> Result<Self, glib::Error> {
+        let node_info = DBusNodeInfo::for_xml(INTROSPECTION_XML)?;
+        let interface_info = node_info
+            .lookup_interface(INTERFACE)
+            .expect("Notifications interface is missing from the introspection data");
+
+        let next_id = Rc::new(Cell::new(1u32));
+        connection
+            .register_object(PATH, &interface_info)
+            .method_call(
+                move |_, _, _, _, method, parameters, invocation| match method {
+                    "Notify" => {
+                        let Some(mut notification) = Notification::from_parameters(&parameters)
+                        else {
+                            invocation.return_dbus_error(
+                                "org.freedesktop.DBus.Error.InvalidArgs",
+                                "Invalid Notify parameters",
+                            );
+                            return;
+                        };
+
+                        if notification.replaces_id == 0 {
+                            notification.id = next_id.get();
+                            next_id.set(next_id.get().wrapping_add(1).max(1));
+                        }
+
+                        invocation.return_value(Some(&(notification.id,).to_variant()));
+                        f(ServerEvent::Notify(notification));
+                    }
+                    "CloseNotification" => {
+                        if let Some((id,)) = parameters.get::<(u32,)>() {
+                            f(ServerEvent::Close(id));
+                        }
+                        invocation.return_value(None);
+                    }
+                    "GetCapabilities" => {
+                        let capabilities = vec!["actions", "body", "persistence"];
+                        invocation.return_value(Some(&(capabilities,).to_variant()));
+                    }
+                    "GetServerInformation" => {
+                        invocation.return_value(Some(
+                            &("crabbar", "crabbar", env!("CARGO_PKG_VERSION"), "1.2").to_variant(),
+                        ));
+                    }
+                    _ => invocation.return_dbus_error(
+                        "org.freedesktop.DBus.Error.UnknownMethod",
+                        &format!("Unknown method {method}"),
+                    ),
+                },
+            )
+            .build()?;
+
+        gio::bus_own_name_on_connection(
+            connection,
+            NAME,
+            BusNameOwnerFlags::NONE,
+            |_, _| {},
+            |_, name| eprintln!("Failed to own {name}, is another notification daemon running?"),
+        );
+
+        Ok(Self {
+            connection: connection.clone(),
+        })
+    }
+
+    fn emit(&self, signal: &str, parameters: Variant) {
+        if let Err(err) =
+            self.connection
+                .emit_signal(None, PATH, INTERFACE, signal, Some(&parameters))
+        {
+            eprintln!("Failed to emit {signal}: {err}");
+        }
+    }
+
+    pub fn closed(&self, id: u32, reason: CloseReason) {
+        self.emit("NotificationClosed", (id, reason as u32).to_variant());
+    }
+
+    pub fn action_invoked(&self, id: u32, key: &str) {
+        self.emit("ActionInvoked", (id, key).to_variant());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{connect, wait_for, with_test_bus};
+    use relm4::gtk::gio::{DBusCallFlags, DBusSignalFlags};
+    use std::cell::RefCell;
+
+    fn parameters(actions: &[&str], hints: &VariantDict, expire_timeout: i32) -> Variant {
+        // Tuples of variants would box the hints
+        Variant::tuple_from_iter([
+            "app".to_variant(),
+            0u32.to_variant(),
+            "".to_variant(),
+            "Summary".to_variant(),
+            "Body".to_variant(),
+            actions.to_variant(),
+            hints.end(),
+            expire_timeout.to_variant(),
+        ])
+    }
+
+    #[test]
+    fn notify_parameters() {
+        let hints = VariantDict::new(None);
+        hints.insert_value("urgency", &2u8.to_variant());
+        hints.insert_value("image-path", &"/tmp/image.png".to_variant());
+
+        let notification =
+            Notification::from_parameters(&parameters(&["default", "Open", "odd"], &hints, 0))
+                .unwrap();
+
+        assert_eq!(notification.app_name, "app");
+        assert_eq!(notification.app_icon, "/tmp/image.png");
+        assert_eq!(notification.summary, "Summary");
+        assert_eq!(notification.urgency, Urgency::Critical);
+        assert_eq!(notification.expire_timeout, 0);
+        assert_eq!(
+            notification.actions,
+            vec![Action {
+                key: "default".to_string(),
+                label: "Open".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(Notification::from_parameters(&("app",).to_variant()).is_none());
+    }
+
+    async fn call(connection: &DBusConnection, method: &str, parameters: Variant) -> Variant {
+        connection
+            .call_future(
+                Some(NAME),
+                PATH,
+                INTERFACE,
+                method,
+                Some(&parameters),
+                None,
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn server_on_test_bus() {
+        with_test_bus(|address| async move {
+            let server_connection = connect(&address).await;
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let server = NotificationServer::start(&server_connection, {
+                let events = events.clone();
+                move |event| events.borrow_mut().push(event)
+            })
+            .unwrap();
+
+            // The name is requested before this call is sent, so it's owned once it returns
+            server_connection
+                .call_future(
+                    Some("org.freedesktop.DBus"),
+                    "/org/freedesktop/DBus",
+                    "org.freedesktop.DBus",
+                    "GetId",
+                    None,
+                    None,
+                    DBusCallFlags::NONE,
+                    -1,
+                )
+                .await
+                .unwrap();
+
+            let connection = connect(&address).await;
+            let closed = Rc::new(RefCell::new(Vec::new()));
+            connection.signal_subscribe(
+                None,
+                Some(INTERFACE),
+                Some("NotificationClosed"),
+                Some(PATH),
+                None,
+                DBusSignalFlags::NONE,
+                {
+                    let closed = closed.clone();
+                    move |_, _, _, _, _, parameters| {
+                        closed.borrow_mut().extend(parameters.get::<(u32, u32)>());
+                    }
+                },
+            );
+
+            let hints = VariantDict::new(None);
+            let notify = || call(&connection, "Notify", parameters(&[], &hints, -1));
+            assert_eq!(notify().await.get::<(u32,)>(), Some((1,)));
+            assert_eq!(notify().await.get::<(u32,)>(), Some((2,)));
+            assert_eq!(events.borrow().len(), 2);
+            assert!(matches!(
+                events.borrow()[1],
+                ServerEvent::Notify(Notification { id: 2, .. })
+            ));
+
+            call(&connection, "CloseNotification", (1u32,).to_variant()).await;
+            assert!(matches!(events.borrow()[2], ServerEvent::Close(1)));
+
+            server.closed(1, CloseReason::Closed);
+            wait_for(|| !closed.borrow().is_empty()).await;
+            assert_eq!(*closed.borrow(), [(1, CloseReason::Closed as u32)]);
+        });
+    }
+}
@@ -0,0 +1,141 @@
+use relm4::gtk::{
+    gio::{prelude::*, DBusCallFlags, DBusConnection, DBusProxy, DBusProxyFlags},
+    glib::{self, Variant, VariantDict},
+};
+use std::rc::Rc;
+
+// https://gitlab.freedesktop.org/upower/power-profiles-daemon
+const NAME: &str = "net.hadess.PowerProfiles";
+const PATH: &str = "/net/hadess/PowerProfiles";
+const INTERFACE: &str = "net.hadess.PowerProfiles";
+
+/// Reads the profile names from the `aa{sv}` of the `Profiles` property
+fn parse_profiles(profiles: &Variant) -> Vec<String> {
+    profiles
+        .iter()
+        .filter_map(|profile| {
+            VariantDict::new(Some(&profile))
+                .lookup::<String>("Profile")
+                .ok()
+                .flatten()
+        })
+        .collect()
+}
+
+/// The profile after (or before) the active one, wrapping around
+pub fn cycle_profile<'a>(profiles: &'a [String], active: &str, forward: bool) -> Option<&'a str> {
+    let len = profiles.len();
+    let idx = profiles.iter().position(|profile| profile == active);
+    let next = match (idx, forward) {
+        (Some(idx), true) => (idx + 1) % len,
+        (Some(idx), false) => (idx + len - 1) % len,
+        (None, _) => 0,
+    };
+
+    profiles.get(next).map(String::as_str)
+}
+
+#[derive(Debug, Clone)]
+pub struct PowerProfiles {
+    proxy: DBusProxy,
+}
+
+impl PowerProfiles {
+    pub async fn new(connection: &DBusConnection) -> Result<Self, glib::Error> {
+        let proxy = DBusProxy::new_future(
+            connection,
+            DBusProxyFlags::NONE,
+            None,
+            Some(NAME),
+            PATH,
+            INTERFACE,
+        )
+        .await?;
+
+        Ok(Self { proxy })
+    }
+
+    /// Empty if power-profiles-daemon isn't running
+    pub fn active_profile(&self) -> String {
+        self.proxy
+            .cached_property("ActiveProfile")
+            .and_then(|profile| profile.get())
+            .unwrap_or_default()
+    }
+
+    pub fn profiles(&self) -> Vec<String> {
+        self.proxy
+            .cached_property("Profiles")
+            .map(|profiles| parse_profiles(&profiles))
+            .unwrap_or_default()
+    }
+
+    /// Calls `f` after the cached properties were updated, including when the daemon starts or
+    /// stops
+    pub fn connect_changed<F: Fn() + 'static>(&self, f: F) {
+        let f = Rc::new(f);
+        self.proxy.connect_local("g-properties-changed", false, {
+            let f = f.clone();
+            move |_| {
+                f();
+                None
+            }
+        });
+        self.proxy
+            .connect_notify_local(Some("g-name-owner"), move |_, _| f());
+    }
+
+    pub async fn set_active_profile(&self, profile: &str) -> Result<(), glib::Error> {
+        self.proxy
+            .call_future(
+                "org.freedesktop.DBus.Properties.Set",
+                Some(&(INTERFACE, "ActiveProfile", profile.to_variant()).to_variant()),
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles() {
+        let profiles = ["power-saver", "balanced", "performance"].map(|profile| {
+            let dict = VariantDict::new(None);
+            dict.insert_value("Profile", &profile.to_variant());
+            dict.insert_value("Driver", &"platform_profile".to_variant());
+            dict.end()
+        });
+        let profiles = Variant::array_from_iter_with_type(glib::VariantTy::VARDICT, profiles);
+
+        assert_eq!(
+            parse_profiles(&profiles),
+            vec!["power-saver", "balanced", "performance"]
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let profiles = ["power-saver", "balanced", "performance"].map(String::from);
+
+        assert_eq!(
+            cycle_profile(&profiles, "balanced", true),
+            Some("performance")
+        );
+        assert_eq!(
+            cycle_profile(&profiles, "performance", true),
+            Some("power-saver")
+        );
+        assert_eq!(
+            cycle_profile(&profiles, "power-saver", false),
+            Some("performance")
+        );
+        assert_eq!(cycle_profile(&profiles, "", true), Some("power-saver"));
+        assert_eq!(cycle_profile(&[], "balanced", true), None);
+    }
+}
@@ -0,0 +1,246 @@
+use relm4::gtk::{
+    gio::{prelude::*, DBusCallFlags, DBusConnection, DBusProxy, DBusProxyFlags},
+    glib::{self, Variant, VariantDict},
+};
+
+// https://github.com/AyatanaIndicators/libdbusmenu/blob/master/libdbusmenu-glib/dbus-menu.xml
+const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleType {
+    Checkmark,
+    Radio,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuItem {
+    pub id: i32,
+    pub label: String,
+    pub enabled: bool,
+    pub visible: bool,
+    pub separator: bool,
+    pub toggle: Option<(ToggleType, bool)>,
+    pub children: Vec<MenuItem>,
+}
+
+/// Parses a `(ia{sv}av)` layout node, properties missing from it have their default value
+fn parse_layout(layout: &Variant) -> Option<MenuItem> {
+    let id = layout.try_child_value(0)?.get::<i32>()?;
+    let properties = VariantDict::new(Some(&layout.try_child_value(1)?));
+    let children = layout.try_child_value(2)?;
+
+    let string = |key: &str| properties.lookup::<String>(key).ok().flatten();
+    let toggle_type = match string("toggle-type").as_deref() {
+        Some("checkmark") => Some(ToggleType::Checkmark),
+        Some("radio") => Some(ToggleType::Radio),
+        _ => None,
+    };
+    let toggle_state = properties
+        .lookup::<i32>("toggle-state")
+        .ok()
+        .flatten()
+        .unwrap_or(0);
+
+    Some(MenuItem {
+        id,
+        label: string("label").unwrap_or_default(),
+        enabled: properties
+            .lookup::<bool>("enabled")
+            .ok()
+            .flatten()
+            .unwrap_or(true),
+        visible: properties
+            .lookup::<bool>("visible")
+            .ok()
+            .flatten()
+            .unwrap_or(true),
+        separator: string("type").as_deref() == Some("separator"),
+        toggle: toggle_type.map(|toggle_type| (toggle_type, toggle_state == 1)),
+        // Children are variants wrapping the same structure
+        children: children
+            .iter()
+            .filter_map(|child| parse_layout(&child.as_variant()?))
+            .collect(),
+    })
+}
+
+/// Reads the new toggle states from the parameters of `ItemsPropertiesUpdated`, removed
+/// properties fall back to unchecked
+fn parse_toggle_states(parameters: &Variant) -> Vec<(i32, bool)> {
+    let Some((updated, removed)) =
+        parameters.get::<(Vec<(i32, VariantDict)>, Vec<(i32, Vec<String>)>)>()
+    else {
+        return Vec::new();
+    };
+
+    let updated = updated.into_iter().filter_map(|(id, properties)| {
+        let state = properties.lookup::<i32>("toggle-state").ok().flatten()?;
+        Some((id, state == 1))
+    });
+    let removed = removed
+        .into_iter()
+        .filter(|(_, properties)| properties.iter().any(|property| property == "toggle-state"))
+        .map(|(id, _)| (id, false));
+
+    updated.chain(removed).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct Menu {
+    proxy: DBusProxy,
+}
+
+impl Menu {
+    pub async fn new(
+        connection: &DBusConnection,
+        bus_name: &str,
+        path: &str,
+    ) -> Result<Self, glib::Error> {
+        let proxy = DBusProxy::new_future(
+            connection,
+            DBusProxyFlags::DO_NOT_LOAD_PROPERTIES,
+            None,
+            Some(bus_name),
+            path,
+            MENU_INTERFACE,
+        )
+        .await?;
+
+        Ok(Self { proxy })
+    }
+
+    /// Returns the root item of the whole menu
+    pub async fn layout(&self) -> Result<Option<MenuItem>, glib::Error> {
+        // Some apps only fill their menu after being told it's about to be shown
+        if let Err(err) = self
+            .proxy
+            .call_future(
+                "AboutToShow",
+                Some(&(0i32,).to_variant()),
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await
+        {
+            eprintln!("AboutToShow failed: {err}");
+        }
+
+        let parameters = (0i32, -1i32, Vec::<String>::new()).to_variant();
+        let response = self
+            .proxy
+            .call_future("GetLayout", Some(&parameters), DBusCallFlags::NONE, -1)
+            .await?;
+
+        // (revision, layout)
+        Ok(parse_layout(&response.child_value(1)))
+    }
+
+    /// Calls `f` with the ids and new states of items whose toggle state changed
+    pub fn connect_toggles_changed<F: Fn(Vec<(i32, bool)>) + 'static>(&self, f: F) {
+        self.proxy.connect_local("g-signal", false, move |values| {
+            let signal = values.get(2).and_then(|value| value.get::<String>().ok());
+            if signal.as_deref() != Some("ItemsPropertiesUpdated") {
+                return None;
+            }
+
+            let toggles = values
+                .get(3)
+                .and_then(|value| value.get::<Variant>().ok())
+                .map(|parameters| parse_toggle_states(&parameters))
+                .unwrap_or_default();
+            if !toggles.is_empty() {
+                f(toggles);
+            }
+            None
+        });
+    }
+
+    pub async fn click(&self, id: i32) -> Result<(), glib::Error> {
+        let timestamp = glib::monotonic_time() as u32;
+        // Event data is unused for clicks, but has to be a variant
+        let parameters = (id, "clicked", 0i32.to_variant(), timestamp).to_variant();
+        self.proxy
+            .call_future("Event", Some(&parameters), DBusCallFlags::NONE, -1)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: i32, properties: &[(&str, Variant)], children: Vec<Variant>) -> Variant {
+        let dict = VariantDict::new(None);
+        for (key, value) in properties {
+            dict.insert_value(key, value);
+        }
+
+        // Tuples of variants would box the properties too
+        Variant::tuple_from_iter([id.to_variant(), dict.end(), children.to_variant()])
+    }
+
+    #[test]
+    fn layout_with_defaults_and_children() {
+        let layout = node(
+            0,
+            &[("children-display", "submenu".to_variant())],
+            vec![
+                node(1, &[("label", "_Open".to_variant())], Vec::new()),
+                node(2, &[("type", "separator".to_variant())], Vec::new()),
+                node(
+                    3,
+                    &[
+                        ("label", "Notifications".to_variant()),
+                        ("toggle-type", "checkmark".to_variant()),
+                        ("toggle-state", 1i32.to_variant()),
+                        ("enabled", false.to_variant()),
+                    ],
+                    Vec::new(),
+                ),
+            ],
+        );
+
+        let root = parse_layout(&layout).unwrap();
+        assert_eq!(root.id, 0);
+        assert_eq!(root.children.len(), 3);
+
+        let open = &root.children[0];
+        assert_eq!(open.label, "_Open");
+        assert!(open.enabled && open.visible && !open.separator);
+        assert_eq!(open.toggle, None);
+
+        assert!(root.children[1].separator);
+
+        let notifications = &root.children[2];
+        assert!(!notifications.enabled);
+        assert_eq!(notifications.toggle, Some((ToggleType::Checkmark, true)));
+    }
+
+    #[test]
+    fn toggle_states() {
+        let updated = [
+            node(1, &[("toggle-state", 1i32.to_variant())], Vec::new()),
+            node(2, &[("toggle-state", 0i32.to_variant())], Vec::new()),
+            node(3, &[("label", "Other".to_variant())], Vec::new()),
+        ]
+        .into_iter()
+        // Only the id and the properties, without children
+        .map(|node| Variant::tuple_from_iter([node.child_value(0), node.child_value(1)]));
+        let updated =
+            Variant::array_from_iter_with_type(glib::VariantTy::new("(ia{sv})").unwrap(), updated);
+        let removed = vec![
+            (4i32, vec!["toggle-state".to_string()]),
+            (5, vec!["label".to_string()]),
+        ]
+        .to_variant();
+        let parameters = Variant::tuple_from_iter([updated, removed]);
+
+        assert_eq!(
+            parse_toggle_states(&parameters),
+            [(1, true), (2, false), (4, false)]
+        );
+        assert!(parse_toggle_states(&().to_variant()).is_empty());
+    }
+}
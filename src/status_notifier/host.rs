@@ -0,0 +1,118 @@
+use relm4::gtk::{
+    gio::{
+        self, prelude::*, BusNameOwnerFlags, BusNameWatcherFlags, DBusCallFlags, DBusConnection,
+        DBusSignalFlags,
+    },
+    glib::{self, MainContext},
+};
+use std::rc::Rc;
+
+use super::watcher::{WATCHER_INTERFACE, WATCHER_NAME, WATCHER_PATH};
+
+#[derive(Debug)]
+pub enum HostEvent {
+    ItemRegistered(String),
+    ItemUnregistered(String),
+    /// All items are gone until a watcher appears again
+    WatcherVanished,
+}
+
+async fn register_host(
+    connection: &DBusConnection,
+    host_name: &str,
+) -> Result<Vec<String>, glib::Error> {
+    connection
+        .call_future(
+            Some(WATCHER_NAME),
+            WATCHER_PATH,
+            WATCHER_INTERFACE,
+            "RegisterStatusNotifierHost",
+            Some(&(host_name,).to_variant()),
+            None,
+            DBusCallFlags::NONE,
+            -1,
+        )
+        .await?;
+
+    let items = connection
+        .call_future(
+            Some(WATCHER_NAME),
+            WATCHER_PATH,
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            Some(&(WATCHER_INTERFACE, "RegisteredStatusNotifierItems").to_variant()),
+            None,
+            DBusCallFlags::NONE,
+            -1,
+        )
+        .await?;
+
+    // (v)
+    Ok(items
+        .child_value(0)
+        .as_variant()
+        .and_then(|items| items.get::<Vec<String>>())
+        .unwrap_or_default())
+}
+
+/// Registers a host with the watcher, calling `f` for items that are already registered and
+/// whenever items come and go. The host registers again if the watcher is replaced.
+pub fn start_host<F: Fn(HostEvent) + 'static>(connection: &DBusConnection, f: F) {
+    let f = Rc::new(f);
+    let host_name = format!("org.kde.StatusNotifierHost-{}", std::process::id());
+
+    gio::bus_own_name_on_connection(
+        connection,
+        &host_name,
+        BusNameOwnerFlags::NONE,
+        |_, _| {},
+        |_, name| eprintln!("Failed to own {name}"),
+    );
+
+    connection.signal_subscribe(
+        Some(WATCHER_NAME),
+        Some(WATCHER_INTERFACE),
+        None,
+        Some(WATCHER_PATH),
+        None,
+        DBusSignalFlags::NONE,
+        {
+            let f = f.clone();
+            move |_, _, _, _, signal, parameters| {
+                let Some((item,)) = parameters.get::<(String,)>() else {
+                    return;
+                };
+
+                match signal {
+                    "StatusNotifierItemRegistered" => f(HostEvent::ItemRegistered(item)),
+                    "StatusNotifierItemUnregistered" => f(HostEvent::ItemUnregistered(item)),
+                    _ => {}
+                }
+            }
+        },
+    );
+
+    gio::bus_watch_name_on_connection(
+        connection,
+        WATCHER_NAME,
+        BusNameWatcherFlags::NONE,
+        {
+            let f = f.clone();
+            move |connection, _, _| {
+                let f = f.clone();
+                let host_name = host_name.clone();
+                MainContext::default().spawn_local(async move {
+                    match register_host(&connection, &host_name).await {
+                        Ok(items) => {
+                            for item in items {
+                                f(HostEvent::ItemRegistered(item));
+                            }
+                        }
+                        Err(err) => eprintln!("Failed to register tray host: {err}"),
+                    }
+                });
+            }
+        },
+        move |_, _| f(HostEvent::WatcherVanished),
+    );
+}
@@ -0,0 +1,195 @@
+use relm4::gtk::{
+    gio::{prelude::*, DBusCallFlags, DBusConnection, DBusProxy, DBusProxyFlags},
+    glib::{self, Variant, VariantDict},
+};
+
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+
+/// ARGB32 image in network byte order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pixmap {
+    pub width: i32,
+    pub height: i32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ItemStatus {
+    Passive,
+    #[default]
+    Active,
+    NeedsAttention,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ItemProperties {
+    pub title: String,
+    pub status: ItemStatus,
+    pub icon_name: String,
+    pub icon_pixmaps: Vec<Pixmap>,
+    pub attention_icon_name: String,
+    pub attention_icon_pixmaps: Vec<Pixmap>,
+    pub icon_theme_path: String,
+    pub tooltip: String,
+    /// Object path of the dbusmenu
+    pub menu: Option<String>,
+    /// The item only supports the context menu, activating should show it
+    pub item_is_menu: bool,
+}
+
+fn parse_pixmaps(value: Option<Variant>) -> Vec<Pixmap> {
+    value
+        .and_then(|value| value.get::<Vec<(i32, i32, Vec<u8>)>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(width, height, data)| {
+            *width > 0 && *height > 0 && data.len() == (*width * *height * 4) as usize
+        })
+        .map(|(width, height, data)| Pixmap {
+            width,
+            height,
+            data,
+        })
+        .collect()
+}
+
+impl ItemProperties {
+    fn from_variant(properties: &Variant) -> Self {
+        let properties = VariantDict::new(Some(properties));
+        let string = |key: &str| {
+            properties
+                .lookup::<String>(key)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+
+        let status = match string("Status").as_str() {
+            "Passive" => ItemStatus::Passive,
+            "NeedsAttention" => ItemStatus::NeedsAttention,
+            _ => ItemStatus::Active,
+        };
+
+        // (icon name, icon pixmaps, title, description)
+        let tooltip = properties
+            .lookup_value("ToolTip", None)
+            .and_then(|tooltip| tooltip.try_child_value(2))
+            .and_then(|title| title.get::<String>())
+            .unwrap_or_default();
+
+        let menu = properties
+            .lookup_value("Menu", None)
+            .and_then(|menu| menu.get::<glib::variant::ObjectPath>())
+            .map(|menu| menu.as_str().to_string())
+            .filter(|menu| menu != "/");
+
+        Self {
+            title: string("Title"),
+            status,
+            icon_name: string("IconName"),
+            icon_pixmaps: parse_pixmaps(properties.lookup_value("IconPixmap", None)),
+            attention_icon_name: string("AttentionIconName"),
+            attention_icon_pixmaps: parse_pixmaps(
+                properties.lookup_value("AttentionIconPixmap", None),
+            ),
+            icon_theme_path: string("IconThemePath"),
+            tooltip,
+            menu,
+            item_is_menu: properties
+                .lookup::<bool>("ItemIsMenu")
+                .ok()
+                .flatten()
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    /// `bus_name/object/path` as registered with the watcher
+    pub id: String,
+    pub bus_name: String,
+    proxy: DBusProxy,
+}
+
+impl Item {
+    pub async fn new(connection: &DBusConnection, id: String) -> Result<Self, glib::Error> {
+        let (bus_name, path) = match id.find('/') {
+            Some(idx) => (id[..idx].to_string(), id[idx..].to_string()),
+            None => (id.clone(), "/StatusNotifierItem".to_string()),
+        };
+
+        // Properties are fetched manually, items only signal that something changed
+        let proxy = DBusProxy::new_future(
+            connection,
+            DBusProxyFlags::DO_NOT_LOAD_PROPERTIES,
+            None,
+            Some(&bus_name),
+            &path,
+            ITEM_INTERFACE,
+        )
+        .await?;
+
+        Ok(Self {
+            id,
+            bus_name,
+            proxy,
+        })
+    }
+
+    pub async fn properties(&self) -> Result<ItemProperties, glib::Error> {
+        let properties = self
+            .proxy
+            .connection()
+            .call_future(
+                Some(&self.bus_name),
+                &self.proxy.object_path(),
+                "org.freedesktop.DBus.Properties",
+                "GetAll",
+                Some(&(ITEM_INTERFACE,).to_variant()),
+                None,
+                DBusCallFlags::NONE,
+                -1,
+            )
+            .await?;
+
+        Ok(ItemProperties::from_variant(&properties.child_value(0)))
+    }
+
+    /// Calls `f` when the item signals a new icon, status, title, tooltip or menu
+    pub fn connect_changed<F: Fn() + 'static>(&self, f: F) {
+        self.proxy.connect_local("g-signal", false, move |values| {
+            let signal = values.get(2).and_then(|value| value.get::<String>().ok());
+            if signal.is_some_and(|signal| signal.starts_with("New")) {
+                f();
+            }
+            None
+        });
+    }
+
+    async fn call(&self, method: &str, parameters: Variant) -> Result<(), glib::Error> {
+        self.proxy
+            .call_future(method, Some(&parameters), DBusCallFlags::NONE, -1)
+            .await?;
+
+        Ok(())
+    }
+
+    // Positions are unknown on Wayland, items are given the origin
+    pub async fn activate(&self) -> Result<(), glib::Error> {
+        self.call("Activate", (0i32, 0i32).to_variant()).await
+    }
+
+    pub async fn secondary_activate(&self) -> Result<(), glib::Error> {
+        self.call("SecondaryActivate", (0i32, 0i32).to_variant())
+            .await
+    }
+
+    pub async fn context_menu(&self) -> Result<(), glib::Error> {
+        self.call("ContextMenu", (0i32, 0i32).to_variant()).await
+    }
+
+    pub async fn scroll(&self, delta: i32) -> Result<(), glib::Error> {
+        self.call("Scroll", (delta, "vertical").to_variant()).await
+    }
+}
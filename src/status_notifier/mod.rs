@@ -0,0 +1,9 @@
+mod dbusmenu;
+mod host;
+mod item;
+mod watcher;
+
+pub use crate::status_notifier::dbusmenu::*;
+pub use crate::status_notifier::host::*;
+pub use crate::status_notifier::item::*;
+pub use crate::status_notifier::watcher::*;
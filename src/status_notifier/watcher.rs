@@ -0,0 +1,178 @@
+use relm4::gtk::{
+    gio::{self, prelude::*, BusNameOwnerFlags, DBusConnection, DBusNodeInfo, DBusSignalFlags},
+    glib::{self, Variant},
+};
+use std::{cell::RefCell, rc::Rc};
+
+// https://www.freedesktop.org/wiki/Specifications/StatusNotifierItem/StatusNotifierWatcher/
+pub const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+pub const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+pub const WATCHER_INTERFACE: &str = "org.kde.StatusNotifierWatcher";
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="org.kde.StatusNotifierWatcher">
+    <method name="RegisterStatusNotifierItem">
+      <arg name="service" type="s" direction="in"/>
+    </method>
+    <method name="RegisterStatusNotifierHost">
+      <arg name="service" type="s" direction="in"/>
+    </method>
+    <property name="RegisteredStatusNotifierItems" type="as" access="read"/>
+    <property name="IsStatusNotifierHostRegistered" type="b" access="read"/>
+    <property name="ProtocolVersion" type="i" access="read"/>
+    <signal name="StatusNotifierItemRegistered">
+      <arg name="service" type="s"/>
+    </signal>
+    <signal name="StatusNotifierItemUnregistered">
+      <arg name="service" type="s"/>
+    </signal>
+    <signal name="StatusNotifierHostRegistered"/>
+  </interface>
+</node>
+"#;
+
+#[derive(Default)]
+struct WatcherState {
+    /// Items as `bus_name/object/path`
+    items: Vec<String>,
+    hosts: Vec<String>,
+}
+
+fn emit(connection: &DBusConnection, signal: &str, parameters: Option<&Variant>) {
+    if let Err(err) =
+        connection.emit_signal(None, WATCHER_PATH, WATCHER_INTERFACE, signal, parameters)
+    {
+        eprintln!("Failed to emit {signal}: {err}");
+    }
+}
+
+/// Items register either with their bus name or with an object path on the sender's connection
+fn item_id(sender: &str, service: &str) -> String {
+    if service.starts_with('/') {
+        format!("{sender}{service}")
+    } else {
+        format!("{service}/StatusNotifierItem")
+    }
+}
+
+fn handle_method_call(
+    connection: &DBusConnection,
+    state: &RefCell<WatcherState>,
+    sender: &str,
+    method: &str,
+    parameters: &Variant,
+) {
+    let Some((service,)) = parameters.get::<(String,)>() else {
+        return;
+    };
+
+    match method {
+        "RegisterStatusNotifierItem" => {
+            let item = item_id(sender, &service);
+            let mut state = state.borrow_mut();
+            if !state.items.contains(&item) {
+                state.items.push(item.clone());
+                emit(
+                    connection,
+                    "StatusNotifierItemRegistered",
+                    Some(&(item,).to_variant()),
+                );
+            }
+        }
+        "RegisterStatusNotifierHost" => {
+            let mut state = state.borrow_mut();
+            if !state.hosts.iter().any(|host| host == sender) {
+                state.hosts.push(sender.to_string());
+                emit(connection, "StatusNotifierHostRegistered", None);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Removes items and hosts of a bus name that vanished
+fn remove_owner(connection: &DBusConnection, state: &RefCell<WatcherState>, name: &str) {
+    let mut state = state.borrow_mut();
+    state.hosts.retain(|host| host != name);
+
+    let prefix = format!("{name}/");
+    let (removed, items) = state
+        .items
+        .drain(..)
+        .partition::<Vec<_>, _>(|item| item.starts_with(&prefix));
+    state.items = items;
+
+    for item in removed {
+        emit(
+            connection,
+            "StatusNotifierItemUnregistered",
+            Some(&(item,).to_variant()),
+        );
+    }
+}
+
+/// Exports a watcher and requests its name, so that items can register without another tray
+/// running. If the name is taken, the existing watcher is used instead.
+pub fn start_watcher(connection: &DBusConnection) -> Result<(), glib::Error> {
+    let node_info = DBusNodeInfo::for_xml(INTROSPECTION_XML)?;
+    let interface_info = node_info
+        .lookup_interface(WATCHER_INTERFACE)
+        .expect("Watcher interface is missing from the introspection data");
+
+    let state = Rc::new(RefCell::new(WatcherState::default()));
+
+    connection
+        .register_object(WATCHER_PATH, &interface_info)
+        .method_call({
+            let state = state.clone();
+            move |connection, sender, _, _, method, parameters, invocation| {
+                handle_method_call(
+                    &connection,
+                    &state,
+                    sender.unwrap_or_default(),
+                    method,
+                    &parameters,
+                );
+                invocation.return_value(None);
+            }
+        })
+        .property({
+            let state = state.clone();
+            move |_, _, _, _, property| {
+                let state = state.borrow();
+                match property {
+                    "RegisteredStatusNotifierItems" => state.items.to_variant(),
+                    "IsStatusNotifierHostRegistered" => (!state.hosts.is_empty()).to_variant(),
+                    _ => 0i32.to_variant(),
+                }
+            }
+        })
+        .build()?;
+
+    connection.signal_subscribe(
+        Some("org.freedesktop.DBus"),
+        Some("org.freedesktop.DBus"),
+        Some("NameOwnerChanged"),
+        Some("/org/freedesktop/DBus"),
+        None,
+        DBusSignalFlags::NONE,
+        move |connection, _, _, _, _, parameters| {
+            if let Some((name, _, new_owner)) = parameters.get::<(String, String, String)>() {
+                if new_owner.is_empty() {
+                    remove_owner(connection, &state, &name);
+                }
+            }
+        },
+    );
+
+    gio::bus_own_name_on_connection(
+        connection,
+        WATCHER_NAME,
+        BusNameOwnerFlags::NONE,
+        |_, _| {},
+        |_, name| eprintln!("{name} is owned by another tray, using it instead"),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+pub fn read_to_string_buf<P: AsRef<Path>>(path: P, buffer: &mut String) -> io::Result<&mut String> {
+    let mut file = File::open(path)?;
+
+    buffer.clear();
+    file.read_to_string(buffer)?;
+
+    Ok(buffer)
+}
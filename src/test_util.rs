@@ -0,0 +1,75 @@
+use relm4::gtk::{
+    gio::{DBusConnection, DBusConnectionFlags, TestDBus, TestDBusFlags},
+    glib,
+};
+use std::{
+    env, fs,
+    future::Future,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Runs `f` on a private bus, with its own main context to not interfere with other tests
+pub fn with_test_bus<F: Future<Output = ()>>(f: impl FnOnce(String) -> F) {
+    let bus = TestDBus::new(TestDBusFlags::NONE);
+    bus.up();
+    let address = bus.bus_address().unwrap().to_string();
+
+    let context = glib::MainContext::new();
+    context
+        .with_thread_default(|| context.block_on(f(address)))
+        .unwrap();
+
+    bus.down();
+}
+
+pub async fn connect(address: &str) -> DBusConnection {
+    DBusConnection::for_address_future(
+        address,
+        DBusConnectionFlags::AUTHENTICATION_CLIENT | DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+/// Iterates the main context until `f` returns true, panicking after a second
+pub async fn wait_for(f: impl Fn() -> bool) {
+    for _ in 0..100 {
+        if f() {
+            return;
+        }
+        glib::timeout_future(Duration::from_millis(10)).await;
+    }
+    panic!("Timed out");
+}
+
+/// Empty directory in the temp dir, removed on drop. `name` has to be unique since tests run in
+/// parallel.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("crabbar-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes a file relative to the directory, creating its parents
+    pub fn write(&self, path: impl AsRef<Path>, contents: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
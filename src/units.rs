@@ -0,0 +1,25 @@
+const MIB: f64 = 1024.0 * 1024.0;
+const GIB: f64 = MIB * 1024.0;
+
+/// Formats sizes in binary units, MiB below a GiB
+pub fn format_size(size: u64) -> String {
+    if (size as f64) < GIB {
+        format!("{:.1}MiB", size as f64 / MIB)
+    } else {
+        format!("{:.1}GiB", size as f64 / GIB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_units() {
+        assert_eq!(format_size(0), "0.0MiB");
+        assert_eq!(format_size(512 * 1024), "0.5MiB");
+        assert_eq!(format_size(1024 * 1024 * 1024 - 1), "1024.0MiB");
+        assert_eq!(format_size(1024 * 1024 * 1024), "1.0GiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024 / 2), "1.5GiB");
+    }
+}
@@ -0,0 +1,191 @@
+use relm4::gtk::{
+    gio::{self, prelude::*, DBusCallFlags, DBusConnection, DBusProxy, DBusProxyFlags},
+    glib::{self, variant::FromVariant},
+};
+use std::time::Duration;
+
+use crate::battery::BatteryInfo;
+
+// https://upower.freedesktop.org/docs/Device.html
+const UPOWER_NAME: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const UPOWER_INTERFACE: &str = "org.freedesktop.UPower";
+const DEVICE_INTERFACE: &str = "org.freedesktop.UPower.Device";
+/// Composite of the batteries powering the system
+pub const DISPLAY_DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+
+const TYPE_LINE_POWER: u32 = 1;
+
+/// Maps the `State` property to the sysfs status names
+fn status(state: u32) -> &'static str {
+    match state {
+        1 => "Charging",
+        2 => "Discharging",
+        3 => "Empty",
+        4 => "Full",
+        5 | 6 => "Not charging",
+        _ => "Unknown",
+    }
+}
+
+fn battery_info(percentage: f64, state: u32, time_to_empty: i64, time_to_full: i64) -> BatteryInfo {
+    let status = status(state);
+    // Estimates are 0 when unknown
+    let estimate = match status {
+        "Charging" => time_to_full,
+        "Discharging" => time_to_empty,
+        _ => 0,
+    };
+
+    BatteryInfo {
+        capacity: percentage.round().clamp(0.0, 100.0) as i32,
+        status: status.to_string(),
+        estimate: u64::try_from(estimate)
+            .ok()
+            .filter(|estimate| *estimate != 0)
+            .map(Duration::from_secs),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UPowerDevice {
+    pub path: String,
+    proxy: DBusProxy,
+}
+
+impl UPowerDevice {
+    pub async fn new(connection: &DBusConnection, path: String) -> Result<Self, glib::Error> {
+        let proxy = DBusProxy::new_future(
+            connection,
+            DBusProxyFlags::NONE,
+            None,
+            Some(UPOWER_NAME),
+            &path,
+            DEVICE_INTERFACE,
+        )
+        .await?;
+
+        Ok(Self { path, proxy })
+    }
+
+    fn property<T: FromVariant>(&self, name: &str) -> Option<T> {
+        self.proxy.cached_property(name)?.get()
+    }
+
+    /// Sysfs name of the device, e.g. `BAT0`
+    pub fn native_path(&self) -> String {
+        self.property("NativePath").unwrap_or_default()
+    }
+
+    pub fn model(&self) -> String {
+        self.property("Model").unwrap_or_default()
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.property("IsPresent").unwrap_or_default()
+    }
+
+    /// Batteries of mice, headsets and other devices not powering the system
+    pub fn is_peripheral(&self) -> bool {
+        self.is_present()
+            && !self.property::<bool>("PowerSupply").unwrap_or_default()
+            && self.property::<u32>("Type") != Some(TYPE_LINE_POWER)
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self.property::<u32>("Type").unwrap_or_default() {
+            5 => "󰍽",
+            6 => "󰌌",
+            8 => "󰏲",
+            12 => "󰊴",
+            17 | 19 => "󰋋",
+            18 => "󰓃",
+            _ => "󰂑",
+        }
+    }
+
+    pub fn info(&self) -> BatteryInfo {
+        battery_info(
+            self.property("Percentage").unwrap_or_default(),
+            self.property("State").unwrap_or_default(),
+            self.property("TimeToEmpty").unwrap_or_default(),
+            self.property("TimeToFull").unwrap_or_default(),
+        )
+    }
+
+    /// Calls `f` after the cached properties were updated
+    pub fn connect_changed<F: Fn() + 'static>(&self, f: F) {
+        self.proxy
+            .connect_local("g-properties-changed", false, move |_| {
+                f();
+                None
+            });
+    }
+}
+
+/// Returns the object paths of all devices, without the display device
+pub async fn list_devices(connection: &DBusConnection) -> Result<Vec<String>, glib::Error> {
+    let devices = connection
+        .call_future(
+            Some(UPOWER_NAME),
+            UPOWER_PATH,
+            UPOWER_INTERFACE,
+            "EnumerateDevices",
+            None,
+            None,
+            DBusCallFlags::NONE,
+            -1,
+        )
+        .await?;
+
+    Ok(devices
+        .child_value(0)
+        .iter()
+        .filter_map(|path| path.str().map(String::from))
+        .collect())
+}
+
+/// Calls `f` with the object path and whether the device was added or removed
+pub fn watch_devices<F: Fn(String, bool) + 'static>(connection: &DBusConnection, f: F) {
+    connection.signal_subscribe(
+        Some(UPOWER_NAME),
+        Some(UPOWER_INTERFACE),
+        None,
+        Some(UPOWER_PATH),
+        None,
+        gio::DBusSignalFlags::NONE,
+        move |_, _, _, _, signal, parameters| {
+            let Some(path) = parameters.child_value(0).str().map(String::from) else {
+                return;
+            };
+
+            match signal {
+                "DeviceAdded" => f(path, true),
+                "DeviceRemoved" => f(path, false),
+                _ => {}
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discharging_with_estimate() {
+        let info = battery_info(41.6, 2, 5400, 0);
+        assert_eq!(info.capacity, 42);
+        assert_eq!(info.status, "Discharging");
+        assert_eq!(info.estimate, Some(Duration::from_secs(5400)));
+    }
+
+    #[test]
+    fn unknown_estimate() {
+        let info = battery_info(100.0, 4, 0, 0);
+        assert_eq!(info.status, "Full");
+        assert_eq!(info.estimate, None);
+
+        assert_eq!(battery_info(50.0, 1, 3600, 0).estimate, None);
+    }
+}
@@ -1,45 +1,139 @@
+use regex::Regex;
 use relm4::{
-    gtk::{self, prelude::*},
+    gtk::{self, gio, prelude::*},
     prelude::*,
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, path::Path};
+
+use crate::{
+    config::{ActiveWindowConfig, ActiveWindowDisplay, EllipsisPosition, RewriteRule},
+    desktop_entry::IconResolver,
+};
+
+const DEFAULT_MAX_LENGTH: usize = 60;
+const ELLIPSIS: &str = "...";
 
 #[derive(Debug)]
 pub enum ActiveWindowMsg {
     ActiveWindow { title: String, class: String },
 }
 
+struct CompiledRule {
+    class: Option<String>,
+    regex: Regex,
+    replacement: String,
+}
+
+fn compile_rules(rules: Vec<RewriteRule>) -> Vec<CompiledRule> {
+    rules
+        .into_iter()
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(regex) => Some(CompiledRule {
+                class: rule.class,
+                regex,
+                replacement: rule.replacement,
+            }),
+            Err(err) => {
+                eprintln!("Invalid rewrite rule pattern {}: {err}", rule.pattern);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Applies every rule matching the class in order
+fn rewrite<'a>(rules: &[CompiledRule], class: &str, title: &'a str) -> Cow<'a, str> {
+    let mut title = Cow::Borrowed(title);
+
+    for rule in rules.iter().filter(|rule| {
+        rule.class
+            .as_ref()
+            .is_none_or(|rule_class| rule_class == class)
+    }) {
+        let replaced = match rule.regex.replace_all(&title, rule.replacement.as_str()) {
+            Cow::Owned(replaced) => Some(replaced),
+            Cow::Borrowed(_) => None,
+        };
+
+        if let Some(replaced) = replaced {
+            title = Cow::Owned(replaced);
+        }
+    }
+
+    title
+}
+
+/// Truncates the text to `max_length` characters, not counting the ellipsis
+fn truncate(text: &str, max_length: usize, position: EllipsisPosition) -> Cow<'_, str> {
+    let length = text.chars().count();
+    if length <= max_length {
+        return Cow::Borrowed(text);
+    }
+
+    // Byte index of the n-th character
+    let char_index = |n: usize| {
+        text.char_indices()
+            .nth(n)
+            .map_or(text.len(), |(idx, _)| idx)
+    };
+
+    Cow::Owned(match position {
+        EllipsisPosition::Start => {
+            format!("{ELLIPSIS}{}", &text[char_index(length - max_length)..])
+        }
+        EllipsisPosition::Middle => {
+            let head = max_length.div_ceil(2);
+            let tail = max_length - head;
+            format!(
+                "{}{ELLIPSIS}{}",
+                &text[..char_index(head)],
+                &text[char_index(length - tail)..]
+            )
+        }
+        EllipsisPosition::End => format!("{}{ELLIPSIS}", &text[..char_index(max_length)]),
+    })
+}
+
 pub struct ActiveWindowModel {
-    icon_name: String,
+    icon_resolver: IconResolver,
+    rules: Vec<CompiledRule>,
+    max_length: usize,
+    ellipsis: EllipsisPosition,
+    display: ActiveWindowDisplay,
+    hide_empty: bool,
+
+    icon: String,
+    class: String,
     title: String,
 }
 
 impl ActiveWindowModel {
-    fn window_name(&self) -> Cow<str> {
-        let length = self.title.chars().count();
-
-        // Truncate if over 60 chars
-        if length > 60 {
-            // Get 60th character's index
-            let truncated_length = self
-                .title
-                .char_indices()
-                .enumerate()
-                .take_while(|(a, (_, _))| *a < 60)
-                .last()
-                .unwrap()
-                .1
-                 .0;
-            Cow::Owned(format!("{}...", &self.title[..truncated_length]))
+    fn gicon(&self) -> gio::Icon {
+        // Desktop entries may specify an absolute path instead of an icon name
+        if Path::new(&self.icon).is_absolute() {
+            gio::FileIcon::new(&gio::File::for_path(&self.icon)).upcast()
         } else {
-            Cow::Borrowed(&self.title)
+            gio::ThemedIcon::new(&self.icon).upcast()
         }
     }
+
+    fn window_name(&self) -> String {
+        let name = match self.display {
+            ActiveWindowDisplay::Title => rewrite(&self.rules, &self.class, &self.title),
+            ActiveWindowDisplay::Class => Cow::Borrowed(self.class.as_str()),
+        };
+
+        truncate(&name, self.max_length, self.ellipsis).into_owned()
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.hide_empty || !self.class.is_empty() || !self.title.is_empty()
+    }
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for ActiveWindowModel {
-    type Init = ();
+    type Init = ActiveWindowConfig;
 
     type Input = ActiveWindowMsg;
     type Output = ();
@@ -51,9 +145,12 @@ impl SimpleComponent for ActiveWindowModel {
 
             set_css_classes: &["widget", "active-window"],
 
+            #[watch]
+            set_visible: model.is_visible(),
+
             append: icon = &gtk::Image {
                 #[watch]
-                set_icon_name: Some(&model.icon_name),
+                set_from_gicon: &model.gicon(),
             },
             append: label = &gtk::Label {
                 #[watch]
@@ -64,12 +161,20 @@ impl SimpleComponent for ActiveWindowModel {
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         _sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let model = Self {
-            icon_name: String::new(),
+            icon_resolver: IconResolver::new(config.icon_map.unwrap_or_default()),
+            rules: compile_rules(config.rewrite_rules.unwrap_or_default()),
+            max_length: config.max_length.unwrap_or(DEFAULT_MAX_LENGTH),
+            ellipsis: config.ellipsis.unwrap_or_default(),
+            display: config.display.unwrap_or_default(),
+            hide_empty: config.hide_empty.unwrap_or(false),
+
+            icon: String::new(),
+            class: String::new(),
             title: String::new(),
         };
 
@@ -81,9 +186,89 @@ impl SimpleComponent for ActiveWindowModel {
     fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
         match msg {
             ActiveWindowMsg::ActiveWindow { class, title } => {
-                self.icon_name = class;
+                self.icon = if class.is_empty() {
+                    String::new()
+                } else {
+                    self.icon_resolver.resolve(&class)
+                };
+                self.class = class;
                 self.title = title;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(class: Option<&str>, pattern: &str, replacement: &str) -> RewriteRule {
+        RewriteRule {
+            class: class.map(str::to_owned),
+            pattern: pattern.to_owned(),
+            replacement: replacement.to_owned(),
+        }
+    }
+
+    #[test]
+    fn truncate_short_text_is_borrowed() {
+        let text = "short title";
+        assert!(matches!(
+            truncate(text, 60, EllipsisPosition::End),
+            Cow::Borrowed("short title")
+        ));
+        assert_eq!(truncate("abc", 3, EllipsisPosition::Middle), "abc");
+    }
+
+    #[test]
+    fn truncate_positions() {
+        let text = "abcdefghij";
+        assert_eq!(truncate(text, 4, EllipsisPosition::End), "abcd...");
+        assert_eq!(truncate(text, 4, EllipsisPosition::Start), "...ghij");
+        assert_eq!(truncate(text, 4, EllipsisPosition::Middle), "ab...ij");
+        assert_eq!(truncate(text, 5, EllipsisPosition::Middle), "abc...ij");
+        assert_eq!(truncate(text, 0, EllipsisPosition::End), "...");
+    }
+
+    #[test]
+    fn truncate_multibyte_characters() {
+        let text = "Привет, мир — 世界";
+        assert_eq!(truncate(text, 6, EllipsisPosition::End), "Привет...");
+        assert_eq!(truncate(text, 2, EllipsisPosition::Start), "...世界");
+        assert_eq!(truncate(text, 3, EllipsisPosition::Middle), "Пр...界");
+    }
+
+    #[test]
+    fn rewrite_applies_class_rules() {
+        let rules = compile_rules(vec![
+            rule(Some("firefox"), " — Mozilla Firefox$", ""),
+            rule(None, r"^\((\d+)\) (.*)$", "$2 [$1]"),
+        ]);
+
+        assert_eq!(rewrite(&rules, "firefox", "Docs — Mozilla Firefox"), "Docs");
+        assert_eq!(
+            rewrite(&rules, "kitty", "Docs — Mozilla Firefox"),
+            "Docs — Mozilla Firefox"
+        );
+        assert_eq!(
+            rewrite(&rules, "firefox", "(3) Inbox — Mozilla Firefox"),
+            "Inbox [3]"
+        );
+    }
+
+    #[test]
+    fn rewrite_without_matches_is_borrowed() {
+        let rules = compile_rules(vec![rule(None, "nothing", "")]);
+        assert!(matches!(
+            rewrite(&rules, "kitty", "~/src"),
+            Cow::Borrowed("~/src")
+        ));
+    }
+
+    #[test]
+    fn invalid_rules_are_skipped() {
+        let rules = compile_rules(vec![rule(None, "(unclosed", ""), rule(None, "a", "b")]);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rewrite(&rules, "", "aaa"), "bbb");
+    }
+}
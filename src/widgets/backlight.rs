@@ -0,0 +1,189 @@
+use relm4::{
+    gtk::{
+        self,
+        gio::{self, BusType, DBusConnection},
+        glib::MainContext,
+        prelude::*,
+    },
+    prelude::*,
+};
+
+use crate::{
+    backlight::{Backlight, Brightness},
+    config::BacklightConfig,
+};
+
+const ICONS: [&str; 9] = ["󰃚", "󰃛", "󰃜", "󰃝", "󰃞", "󰃟", "󰃠", "󰃠", "󰃠"];
+
+const DEFAULT_STEP: u32 = 5;
+const DEFAULT_MIN_BRIGHTNESS: u32 = 1;
+
+#[derive(Debug)]
+pub enum BacklightMsg {
+    Connected(DBusConnection),
+    Update,
+    Scroll(f64),
+}
+
+pub struct BacklightModel {
+    backlight: Option<Backlight>,
+    step: u32,
+    min_brightness: u32,
+
+    connection: Option<DBusConnection>,
+    brightness: Option<Brightness>,
+    _monitor: Option<gio::FileMonitor>,
+}
+
+impl BacklightModel {
+    fn format_icon(&self) -> &'static str {
+        let Some(brightness) = self.brightness else {
+            return ICONS[0];
+        };
+
+        let idx = brightness.percentage() as usize * (ICONS.len() - 1) / 100;
+        ICONS[idx.min(ICONS.len() - 1)]
+    }
+
+    fn format(&self) -> String {
+        match self.brightness {
+            Some(brightness) => format!("{} {}%", self.format_icon(), brightness.percentage()),
+            None => self.format_icon().to_string(),
+        }
+    }
+
+    fn read(&mut self) {
+        let Some(ref backlight) = self.backlight else {
+            return;
+        };
+
+        match backlight.read() {
+            Ok(brightness) => self.brightness = Some(brightness),
+            Err(err) => eprintln!("Failed to read the brightness of {}: {err}", backlight.name),
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for BacklightModel {
+    type Init = BacklightConfig;
+
+    type Input = BacklightMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        gtk::Label {
+            set_css_classes: &["widget", "backlight"],
+
+            add_controller = gtk::EventControllerScroll {
+                set_flags: gtk::EventControllerScrollFlags::VERTICAL
+                    | gtk::EventControllerScrollFlags::DISCRETE,
+                connect_scroll[sender] => move |_, _, dy| {
+                    sender.input(BacklightMsg::Scroll(dy));
+                    gtk::glib::Propagation::Stop
+                },
+            },
+
+            #[watch]
+            set_text: &model.format(),
+            #[watch]
+            set_visible: model.brightness.is_some(),
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let backlight = match Backlight::find(config.device.as_deref()) {
+            Ok(Some(backlight)) => Some(backlight),
+            Ok(None) => {
+                eprintln!("No backlight found");
+                None
+            }
+            Err(err) => {
+                eprintln!("Failed to find backlights: {err}");
+                None
+            }
+        };
+
+        let monitor = backlight.as_ref().and_then(|backlight| {
+            let monitor = backlight
+                .monitor()
+                .inspect_err(|err| eprintln!("Failed to watch {}: {err}", backlight.name))
+                .ok()?;
+            monitor.connect_changed({
+                let sender = sender.clone();
+                move |_, _, _, _| sender.input(BacklightMsg::Update)
+            });
+            Some(monitor)
+        });
+
+        let mut model = Self {
+            backlight,
+            step: config.step.unwrap_or(DEFAULT_STEP),
+            min_brightness: config.min_brightness.unwrap_or(DEFAULT_MIN_BRIGHTNESS),
+
+            connection: None,
+            brightness: None,
+            _monitor: monitor,
+        };
+        model.read();
+
+        MainContext::default().spawn_local({
+            let sender = sender.clone();
+            async move {
+                match gio::bus_get_future(BusType::System).await {
+                    Ok(connection) => sender.input(BacklightMsg::Connected(connection)),
+                    Err(err) => eprintln!("Failed to connect to the system bus: {err}"),
+                }
+            }
+        });
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            BacklightMsg::Connected(connection) => self.connection = Some(connection),
+            BacklightMsg::Update => self.read(),
+            BacklightMsg::Scroll(dy) => {
+                let (Some(backlight), Some(connection), Some(brightness)) =
+                    (&self.backlight, &self.connection, &mut self.brightness)
+                else {
+                    return;
+                };
+
+                let step = if dy < 0.0 {
+                    self.step as i32
+                } else {
+                    -(self.step as i32)
+                };
+                let value = brightness.step(step, self.min_brightness);
+                // Fast scrolling shouldn't step from a value the monitor hasn't reported yet
+                brightness.current = value;
+
+                let backlight = backlight.clone();
+                let connection = connection.clone();
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = backlight.set(&connection, value).await {
+                        eprintln!("Failed to set the brightness of {}: {err}", backlight.name);
+                    }
+                });
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
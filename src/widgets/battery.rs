@@ -1,9 +1,19 @@
 use relm4::{
-    gtk::{self, prelude::*},
+    gtk::{
+        self,
+        gio::{self, BusType, DBusConnection},
+        glib::MainContext,
+        prelude::*,
+    },
     prelude::*,
 };
+use std::time::Duration;
 
-use crate::battery::{get_batteries, BatteryInfo};
+use crate::{
+    battery::{get_batteries, BatteryInfo},
+    config::{BatteryBackend, BatteryConfig, PeripheralBatteries},
+    upower::{self, UPowerDevice, DISPLAY_DEVICE_PATH},
+};
 
 const ICONS_CHARGING: [&str; 11] = [
     "󰢟 ", "󰢜 ", "󰂆 ", "󰂇 ", "󰂈 ", "󰢝 ", "󰂉 ", "󰢞 ", "󰂊 ", "󰂋 ", "󰂅 ",
@@ -16,18 +26,54 @@ const ICONS: [&str; 11] = [
 #[derive(Debug)]
 pub enum BatteryMsg {
     Update,
+    UPowerConnected(DBusConnection),
+    DeviceAdded(UPowerDevice),
+    DeviceRemoved(String),
+    DeviceChanged,
+}
+
+pub struct BatteryInit {
+    /// Sysfs name of the battery, the first battery or UPower's display device if not set
+    pub battery_name: Option<String>,
+    pub config: BatteryConfig,
 }
 
 pub struct BatteryModel {
-    battery_name: String,
+    battery_name: Option<String>,
+    backend: BatteryBackend,
+    peripherals: PeripheralBatteries,
+
+    battery_info: Option<BatteryInfo>,
+    /// UPower devices, including the battery and the peripherals
+    devices: Vec<UPowerDevice>,
+    peripheral_list: gtk::Box,
+}
+
+fn format_estimate(estimate: Duration) -> String {
+    let minutes = estimate.as_secs() / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
 
-    battery_info: BatteryInfo,
+/// Reads the battery from sysfs, falling back to the first one if no name is given
+fn read_sysfs(battery_name: Option<&str>) -> Option<BatteryInfo> {
+    let mut batteries = get_batteries();
+    match battery_name {
+        Some(battery_name) => batteries.remove(battery_name),
+        None => {
+            let name = batteries.keys().min()?.clone();
+            batteries.remove(&name)
+        }
+    }
 }
 
 impl BatteryModel {
     fn format_icon(&self) -> &'static str {
-        let status = &self.battery_info.status;
-        let capacity = self.battery_info.capacity;
+        let Some(ref battery_info) = self.battery_info else {
+            return ICONS[0];
+        };
+
+        let status = &battery_info.status;
+        let capacity = battery_info.capacity.clamp(0, 100);
 
         let capacity_norm = f64::from(capacity) / 100.0;
         if status == "Charging" {
@@ -43,13 +89,83 @@ impl BatteryModel {
 
     fn format(&self) -> String {
         let icon = self.format_icon();
-        format!("{}{}%", icon, self.battery_info.capacity)
+        match self.battery_info {
+            Some(ref battery_info) => format!("{}{}%", icon, battery_info.capacity),
+            None => String::new(),
+        }
+    }
+
+    fn format_tooltip(&self) -> Option<String> {
+        let mut lines = Vec::new();
+
+        if let Some(ref battery_info) = self.battery_info {
+            let mut line = format!("{}: {}%", battery_info.status, battery_info.capacity);
+            if let Some(estimate) = battery_info.estimate {
+                let until = if battery_info.status == "Charging" {
+                    "full"
+                } else {
+                    "empty"
+                };
+                line += &format!(", {} until {until}", format_estimate(estimate));
+            }
+            lines.push(line);
+        }
+
+        if self.peripherals == PeripheralBatteries::Tooltip {
+            lines.extend(self.peripheral_devices().map(|device| {
+                format!(
+                    "{} {}: {}%",
+                    device.icon(),
+                    device.model(),
+                    device.info().capacity
+                )
+            }));
+        }
+
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    fn peripheral_devices(&self) -> impl Iterator<Item = &UPowerDevice> {
+        self.devices.iter().filter(|device| device.is_peripheral())
+    }
+
+    fn main_device(&self) -> Option<&UPowerDevice> {
+        self.devices.iter().find(|device| match self.battery_name {
+            Some(ref battery_name) => device.native_path() == *battery_name,
+            None => device.path == DISPLAY_DEVICE_PATH,
+        })
+    }
+
+    fn update_devices(&mut self) {
+        self.battery_info = self
+            .main_device()
+            .filter(|device| device.is_present())
+            .map(UPowerDevice::info);
+
+        if self.peripherals != PeripheralBatteries::Items {
+            return;
+        }
+
+        while let Some(child) = self.peripheral_list.first_child() {
+            self.peripheral_list.remove(&child);
+        }
+
+        for device in self.peripheral_devices() {
+            let label = gtk::Label::new(Some(&format!(
+                "{} {}%",
+                device.icon(),
+                device.info().capacity
+            )));
+            label.set_css_classes(&["peripheral"]);
+            label.set_tooltip_text(Some(&device.model()));
+            self.peripheral_list.append(&label);
+        }
     }
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for BatteryModel {
-    type Init = String;
+    type Init = BatteryInit;
 
     type Input = BatteryMsg;
     type Output = ();
@@ -61,22 +177,55 @@ impl SimpleComponent for BatteryModel {
 
             set_css_classes: &["widget", "battery"],
 
+            #[watch]
+            set_tooltip_text: model.format_tooltip().as_deref(),
+            #[watch]
+            set_visible: model.battery_info.is_some()
+                || model.peripheral_list.first_child().is_some(),
+
             append: label = &gtk::Label {
                 #[watch]
                 set_text: &model.format(),
-            }
+                #[watch]
+                set_visible: model.battery_info.is_some(),
+            },
+            append: &model.peripheral_list,
         }
     }
 
     fn init(
-        battery_name: Self::Init,
+        init: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let battery_info = get_batteries().remove(&battery_name).unwrap();
+        let backend = init.config.backend.unwrap_or_default();
+
+        let battery_info = match backend {
+            BatteryBackend::Sysfs => read_sysfs(init.battery_name.as_deref()),
+            BatteryBackend::Upower => {
+                MainContext::default().spawn_local({
+                    let sender = sender.clone();
+                    async move {
+                        match gio::bus_get_future(BusType::System).await {
+                            Ok(connection) => {
+                                sender.input(BatteryMsg::UPowerConnected(connection));
+                            }
+                            Err(err) => eprintln!("Failed to connect to the system bus: {err}"),
+                        }
+                    }
+                });
+                None
+            }
+        };
+
         let model = Self {
-            battery_name,
+            battery_name: init.battery_name,
+            backend,
+            peripherals: init.config.peripherals.unwrap_or_default(),
+
             battery_info,
+            devices: Vec::new(),
+            peripheral_list: gtk::Box::new(gtk::Orientation::Horizontal, 4),
         };
 
         let widgets = view_output!();
@@ -84,13 +233,65 @@ impl SimpleComponent for BatteryModel {
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
             BatteryMsg::Update => {
-                if let Some(battery_info) = get_batteries().remove(&self.battery_name) {
-                    self.battery_info = battery_info;
+                if self.backend == BatteryBackend::Sysfs {
+                    if let Some(battery_info) = read_sysfs(self.battery_name.as_deref()) {
+                        self.battery_info = Some(battery_info);
+                    }
                 }
             }
+            BatteryMsg::UPowerConnected(connection) => {
+                let add_device = {
+                    let connection = connection.clone();
+                    let sender = sender.clone();
+                    move |path: String| {
+                        let connection = connection.clone();
+                        let sender = sender.clone();
+                        MainContext::default().spawn_local(async move {
+                            match UPowerDevice::new(&connection, path.clone()).await {
+                                Ok(device) => sender.input(BatteryMsg::DeviceAdded(device)),
+                                Err(err) => eprintln!("Failed to get UPower device {path}: {err}"),
+                            }
+                        });
+                    }
+                };
+
+                upower::watch_devices(&connection, {
+                    let add_device = add_device.clone();
+                    let sender = sender.clone();
+                    move |path, added| {
+                        if added {
+                            add_device(path);
+                        } else {
+                            sender.input(BatteryMsg::DeviceRemoved(path));
+                        }
+                    }
+                });
+
+                add_device(DISPLAY_DEVICE_PATH.to_string());
+                MainContext::default().spawn_local(async move {
+                    match upower::list_devices(&connection).await {
+                        Ok(paths) => paths.into_iter().for_each(add_device),
+                        Err(err) => eprintln!("Failed to list UPower devices: {err}"),
+                    }
+                });
+            }
+            BatteryMsg::DeviceAdded(device) => {
+                device.connect_changed({
+                    let sender = sender.clone();
+                    move || sender.input(BatteryMsg::DeviceChanged)
+                });
+                self.devices.retain(|other| other.path != device.path);
+                self.devices.push(device);
+                self.update_devices();
+            }
+            BatteryMsg::DeviceRemoved(path) => {
+                self.devices.retain(|device| device.path != path);
+                self.update_devices();
+            }
+            BatteryMsg::DeviceChanged => self.update_devices(),
         }
     }
 }
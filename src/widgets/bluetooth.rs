@@ -0,0 +1,321 @@
+use relm4::{
+    gtk::{
+        self,
+        gio::{self, BusType},
+        glib::MainContext,
+        prelude::*,
+    },
+    prelude::*,
+};
+
+use crate::{
+    bluetooth::{BluetoothState, Bluez, BluezEvent, Device},
+    config::BluetoothConfig,
+};
+
+#[derive(Debug)]
+pub enum BluetoothMsg {
+    Connected(Bluez),
+    ServiceChanged(bool),
+    Loaded(BluetoothState),
+    Event(BluezEvent),
+    ShowDevices,
+    SetPowered(bool),
+    ToggleDevice(String),
+}
+
+pub struct BluetoothModel {
+    bluez: Option<Bluez>,
+    state: BluetoothState,
+
+    popover: gtk::Popover,
+    power_switch: gtk::Switch,
+    device_list: gtk::Box,
+    /// Powered state and paired devices the device list was last built from
+    shown: Option<(bool, Vec<(String, Device)>)>,
+}
+
+fn format_device(device: &Device) -> String {
+    match device.battery {
+        Some(battery) => format!("{} 󰥉 {battery}%", device.alias),
+        None => device.alias.clone(),
+    }
+}
+
+impl BluetoothModel {
+    fn format_icon(&self) -> &'static str {
+        if !self.state.powered() {
+            "󰂲"
+        } else if self.state.connected_devices().next().is_some() {
+            "󰂱"
+        } else {
+            "󰂯"
+        }
+    }
+
+    fn format(&self) -> String {
+        let devices = self
+            .state
+            .connected_devices()
+            .map(format_device)
+            .collect::<Vec<_>>();
+
+        if devices.is_empty() {
+            self.format_icon().to_string()
+        } else {
+            format!("{} {}", self.format_icon(), devices.join(", "))
+        }
+    }
+
+    fn format_tooltip(&self) -> String {
+        let adapters = self
+            .state
+            .adapters
+            .values()
+            .map(|adapter| {
+                let state = if adapter.powered { "on" } else { "off" };
+                format!("{}: {state}", adapter.alias)
+            })
+            .collect::<Vec<_>>();
+
+        if adapters.is_empty() {
+            "No Bluetooth adapters".to_string()
+        } else {
+            adapters.join("\n")
+        }
+    }
+
+    /// Only rebuilds the device list when what it shows changed, since BlueZ also sends changes of
+    /// unpaired devices
+    fn update_device_list(&mut self, sender: &ComponentSender<Self>) {
+        let powered = self.state.powered();
+        let paired = self
+            .state
+            .devices
+            .iter()
+            .filter(|(_, device)| device.paired)
+            .map(|(path, device)| (path.clone(), device.clone()))
+            .collect::<Vec<_>>();
+        if self
+            .shown
+            .as_ref()
+            .is_some_and(|shown| shown.0 == powered && shown.1 == paired)
+        {
+            return;
+        }
+
+        while let Some(child) = self.device_list.first_child() {
+            self.device_list.remove(&child);
+        }
+
+        self.power_switch.set_active(powered);
+
+        if paired.is_empty() {
+            let empty = gtk::Label::new(Some("No paired devices"));
+            empty.set_css_classes(&["empty"]);
+            self.device_list.append(&empty);
+        }
+
+        for (path, device) in &paired {
+            let content = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+            if !device.icon.is_empty() {
+                content.append(&gtk::Image::from_icon_name(&device.icon));
+            }
+            let label = gtk::Label::new(Some(&format_device(device)));
+            label.set_xalign(0.0);
+            content.append(&label);
+
+            let button = gtk::Button::new();
+            button.set_child(Some(&content));
+            button.set_css_classes(&["device"]);
+            if device.connected {
+                button.add_css_class("connected");
+            }
+            button.set_sensitive(powered);
+
+            button.connect_clicked({
+                let sender = sender.clone();
+                let path = path.clone();
+                move |_| sender.input(BluetoothMsg::ToggleDevice(path.clone()))
+            });
+            self.device_list.append(&button);
+        }
+
+        self.shown = Some((powered, paired));
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for BluetoothModel {
+    type Init = BluetoothConfig;
+
+    type Input = BluetoothMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        root = gtk::Label {
+            set_css_classes: &["widget", "bluetooth"],
+
+            add_controller = gtk::GestureClick {
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(BluetoothMsg::ShowDevices);
+                },
+            },
+
+            #[watch]
+            set_text: &model.format(),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
+            #[watch]
+            set_class_active: ("off", !model.state.powered()),
+            #[watch]
+            set_class_active: ("connected", model.state.connected_devices().next().is_some()),
+            // Hidden without bluetoothd or adapters
+            #[watch]
+            set_visible: !model.state.adapters.is_empty(),
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let power_switch = gtk::Switch::new();
+        power_switch.set_valign(gtk::Align::Center);
+        power_switch.connect_state_set({
+            let sender = sender.clone();
+            move |_, state| {
+                sender.input(BluetoothMsg::SetPowered(state));
+                gtk::glib::Propagation::Proceed
+            }
+        });
+
+        let power_label = gtk::Label::new(Some("Bluetooth"));
+        power_label.set_hexpand(true);
+        power_label.set_xalign(0.0);
+
+        let power_row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        power_row.set_css_classes(&["power"]);
+        power_row.append(&power_label);
+        power_row.append(&power_switch);
+
+        let device_list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+
+        let popover_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+        popover_box.append(&power_row);
+        popover_box.append(&device_list);
+
+        let popover = gtk::Popover::new();
+        popover.set_css_classes(&["bluetooth-devices"]);
+        popover.set_child(Some(&popover_box));
+        popover.set_parent(&root);
+
+        let model = Self {
+            bluez: None,
+            state: BluetoothState::default(),
+
+            popover,
+            power_switch,
+            device_list,
+            shown: None,
+        };
+
+        MainContext::default().spawn_local({
+            let sender = sender.clone();
+            async move {
+                match gio::bus_get_future(BusType::System).await {
+                    Ok(connection) => sender.input(BluetoothMsg::Connected(Bluez::new(connection))),
+                    Err(err) => eprintln!("Failed to connect to the system bus: {err}"),
+                }
+            }
+        });
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            BluetoothMsg::Connected(bluez) => {
+                bluez.watch({
+                    let sender = sender.clone();
+                    move |event| sender.input(BluetoothMsg::Event(event))
+                });
+                bluez.watch_service({
+                    let sender = sender.clone();
+                    move |running| sender.input(BluetoothMsg::ServiceChanged(running))
+                });
+                self.bluez = Some(bluez);
+            }
+            BluetoothMsg::ServiceChanged(false) => self.state = BluetoothState::default(),
+            BluetoothMsg::ServiceChanged(true) => {
+                let Some(bluez) = self.bluez.clone() else {
+                    return;
+                };
+
+                let sender = sender.clone();
+                MainContext::default().spawn_local(async move {
+                    match bluez.state().await {
+                        Ok(state) => sender.input(BluetoothMsg::Loaded(state)),
+                        Err(err) => eprintln!("Failed to get Bluetooth devices: {err}"),
+                    }
+                });
+            }
+            BluetoothMsg::Loaded(state) => self.state = state,
+            BluetoothMsg::Event(event) => self.state.apply(event),
+            BluetoothMsg::ShowDevices => self.popover.popup(),
+            BluetoothMsg::SetPowered(powered) => {
+                // Also emitted when the switch follows the state
+                if powered == self.state.powered() {
+                    return;
+                }
+
+                let Some(bluez) = self.bluez.clone() else {
+                    return;
+                };
+
+                let adapters = self.state.adapters.keys().cloned().collect::<Vec<_>>();
+                MainContext::default().spawn_local(async move {
+                    for adapter in adapters {
+                        if let Err(err) = bluez.set_powered(&adapter, powered).await {
+                            eprintln!("Failed to power {adapter}: {err}");
+                        }
+                    }
+                });
+            }
+            BluetoothMsg::ToggleDevice(path) => {
+                let (Some(bluez), Some(device)) =
+                    (self.bluez.clone(), self.state.devices.get(&path))
+                else {
+                    return;
+                };
+
+                let method = if device.connected {
+                    "Disconnect"
+                } else {
+                    "Connect"
+                };
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = bluez.call_device(&path, method).await {
+                        eprintln!("Failed to {} {path}: {err}", method.to_lowercase());
+                    }
+                });
+            }
+        }
+
+        if self.popover.is_visible() {
+            self.update_device_list(&sender);
+        }
+        self.update_view(widgets, sender);
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeDelta};
+use relm4::{
+    gtk::{self, glib, pango::EllipsizeMode, prelude::*},
+    prelude::*,
+};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::CalendarConfig,
+    ics::{load_events, Event},
+};
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug)]
+pub enum CalendarMsg {
+    Show,
+    Tick,
+    DateChanged,
+}
+
+#[derive(Debug)]
+pub enum CalendarOutput {
+    NextEvent(Option<Event>),
+}
+
+pub struct CalendarModel {
+    ics_paths: Vec<PathBuf>,
+    upcoming_days: u32,
+
+    events: Vec<Event>,
+    loaded_at: Instant,
+}
+
+impl CalendarModel {
+    fn reload(&mut self) {
+        self.events = load_events(&self.ics_paths);
+        self.loaded_at = Instant::now();
+    }
+
+    fn next_event(&self, now: DateTime<Local>) -> Option<&Event> {
+        self.events
+            .iter()
+            .find(|event| !event.all_day && event.start > now)
+    }
+
+    fn selected_date(calendar: &gtk::Calendar) -> Option<NaiveDate> {
+        let date = calendar.date();
+        NaiveDate::from_ymd_opt(
+            date.year(),
+            u32::try_from(date.month()).ok()?,
+            u32::try_from(date.day_of_month()).ok()?,
+        )
+    }
+
+    fn mark_days(&self, calendar: &gtk::Calendar) {
+        calendar.clear_marks();
+
+        let Some(selected) = Self::selected_date(calendar) else {
+            return;
+        };
+
+        for event in &self.events {
+            // Exclusive end, an event ending at midnight doesn't cover the next day
+            let last_day = (event.end - TimeDelta::seconds(1)).max(event.start);
+
+            let mut day = event.start.date_naive();
+            while day <= last_day.date_naive() {
+                if day.year() == selected.year() && day.month() == selected.month() {
+                    calendar.mark_day(day.day());
+                }
+
+                let Some(next_day) = day.succ_opt() else {
+                    break;
+                };
+                day = next_day;
+            }
+        }
+    }
+
+    fn format_event(event: &Event) -> String {
+        if event.all_day {
+            format!("{} All day", event.start.format("%a %d"))
+        } else if event.start.date_naive() == event.end.date_naive() {
+            format!(
+                "{}–{}",
+                event.start.format("%a %d %H:%M"),
+                event.end.format("%H:%M")
+            )
+        } else {
+            format!(
+                "{}–{}",
+                event.start.format("%a %d %H:%M"),
+                event.end.format("%a %d %H:%M")
+            )
+        }
+    }
+
+    /// Lists events from the selected day up to `upcoming_days` ahead
+    fn populate_events(&self, calendar: &gtk::Calendar, list: &gtk::Box) {
+        while let Some(child) = list.first_child() {
+            list.remove(&child);
+        }
+
+        let Some(selected) = Self::selected_date(calendar) else {
+            return;
+        };
+
+        let now = Local::now();
+        let from = if selected == now.date_naive() {
+            now
+        } else {
+            let Some(from) = selected
+                .and_hms_opt(0, 0, 0)
+                .and_then(|time| time.and_local_timezone(Local).earliest())
+            else {
+                return;
+            };
+            from
+        };
+        let to = from + TimeDelta::days(i64::from(self.upcoming_days));
+
+        let mut has_events = false;
+        for event in self
+            .events
+            .iter()
+            .filter(|event| event.end > from && event.start < to)
+        {
+            has_events = true;
+
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+            row.set_css_classes(&["event"]);
+            if event.start <= now && event.end > now {
+                row.add_css_class("ongoing");
+            }
+
+            let time = gtk::Label::new(Some(&Self::format_event(event)));
+            time.set_css_classes(&["time"]);
+            time.set_xalign(0.0);
+            row.append(&time);
+
+            let summary = gtk::Label::new(Some(&event.summary));
+            summary.set_css_classes(&["summary"]);
+            summary.set_hexpand(true);
+            summary.set_xalign(0.0);
+            summary.set_ellipsize(EllipsizeMode::End);
+            summary.set_max_width_chars(32);
+            row.append(&summary);
+
+            list.append(&row);
+        }
+
+        if !has_events {
+            let label = gtk::Label::new(Some("No events"));
+            label.set_css_classes(&["no-events"]);
+            list.append(&label);
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for CalendarModel {
+    type Init = CalendarConfig;
+
+    type Input = CalendarMsg;
+    type Output = CalendarOutput;
+
+    type CommandOutput = ();
+
+    view! {
+        root = gtk::Popover {
+            set_css_classes: &["calendar"],
+
+            connect_show[sender] => move |_| {
+                sender.input(CalendarMsg::Show);
+            },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 8,
+
+                #[name = "calendar"]
+                gtk::Calendar {
+                    set_show_week_numbers: config.show_week_numbers.unwrap_or(false),
+
+                    connect_day_selected[sender] => move |_| {
+                        sender.input(CalendarMsg::DateChanged);
+                    },
+                    connect_next_month[sender] => move |_| {
+                        sender.input(CalendarMsg::DateChanged);
+                    },
+                    connect_prev_month[sender] => move |_| {
+                        sender.input(CalendarMsg::DateChanged);
+                    },
+                    connect_next_year[sender] => move |_| {
+                        sender.input(CalendarMsg::DateChanged);
+                    },
+                    connect_prev_year[sender] => move |_| {
+                        sender.input(CalendarMsg::DateChanged);
+                    },
+                },
+
+                #[name = "events"]
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 4,
+                    set_css_classes: &["events"],
+                },
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = Self {
+            ics_paths: config.ics_paths.clone().unwrap_or_default(),
+            upcoming_days: config.upcoming_days.unwrap_or(7),
+
+            events: Vec::new(),
+            loaded_at: Instant::now(),
+        };
+        model.reload();
+
+        let widgets = view_output!();
+
+        let _ = sender.output(CalendarOutput::NextEvent(
+            model.next_event(Local::now()).cloned(),
+        ));
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            CalendarMsg::Show => {
+                self.reload();
+
+                if let Ok(today) = glib::DateTime::now_local() {
+                    widgets.calendar.select_day(&today);
+                }
+
+                self.mark_days(&widgets.calendar);
+                self.populate_events(&widgets.calendar, &widgets.events);
+            }
+            CalendarMsg::Tick => {
+                if self.loaded_at.elapsed() >= RELOAD_INTERVAL {
+                    self.reload();
+                }
+
+                let _ = sender.output(CalendarOutput::NextEvent(
+                    self.next_event(Local::now()).cloned(),
+                ));
+            }
+            CalendarMsg::DateChanged => {
+                self.mark_days(&widgets.calendar);
+                self.populate_events(&widgets.calendar, &widgets.events);
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
@@ -2,19 +2,31 @@ use relm4::{
     gtk::{self, prelude::*},
     prelude::*,
 };
+use std::{cell::RefCell, rc::Rc};
+use sysinfo::System;
+
+use crate::widgets::processes::{ProcessSort, ProcessesInit, ProcessesModel, ProcessesMsg};
 
 #[derive(Debug)]
 pub enum CpuMsg {
     UpdateUsage { usage: f32 },
+    ShowProcesses,
+}
+
+pub struct CpuInit {
+    pub system: Rc<RefCell<System>>,
+    pub processes_count: usize,
 }
 
 pub struct CpuModel {
     usage: f32,
+
+    processes: Controller<ProcessesModel>,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for CpuModel {
-    type Init = ();
+    type Init = CpuInit;
 
     type Input = CpuMsg;
     type Output = ();
@@ -26,6 +38,12 @@ impl SimpleComponent for CpuModel {
 
             set_css_classes: &["widget", "cpu"],
 
+            add_controller = gtk::GestureClick {
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(CpuMsg::ShowProcesses);
+                },
+            },
+
             append: label = &gtk::Label {
                 #[watch]
                 set_text: &format!(" {:.0}%", model.usage)
@@ -34,11 +52,24 @@ impl SimpleComponent for CpuModel {
     }
 
     fn init(
-        _init: Self::Init,
+        init: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = Self { usage: 0.0 };
+        let processes = ProcessesModel::builder()
+            .launch(ProcessesInit {
+                system: init.system,
+                sort: ProcessSort::Cpu,
+                count: init.processes_count,
+            })
+            .detach();
+        processes.widget().set_parent(&root);
+
+        let model = Self {
+            usage: 0.0,
+
+            processes,
+        };
 
         let widgets = view_output!();
 
@@ -47,7 +78,11 @@ impl SimpleComponent for CpuModel {
 
     fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
         match message {
-            CpuMsg::UpdateUsage { usage } => self.usage = usage,
+            CpuMsg::UpdateUsage { usage } => {
+                self.usage = usage;
+                self.processes.emit(ProcessesMsg::Refresh);
+            }
+            CpuMsg::ShowProcesses => self.processes.widget().popup(),
         }
     }
 }
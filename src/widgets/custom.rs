@@ -0,0 +1,343 @@
+use relm4::{
+    gtk::{
+        self,
+        gio::{self, SubprocessFlags},
+        glib::{self, timeout_future, MainContext, Priority},
+        prelude::*,
+    },
+    prelude::*,
+};
+use serde::Deserialize;
+use std::{ffi::OsStr, time::Duration};
+
+use crate::config::CustomConfig;
+
+/// Delay before restarting a continuously running command that exited
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonClass {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct JsonOutput {
+    #[serde(default)]
+    text: String,
+    tooltip: Option<String>,
+    class: Option<JsonClass>,
+    percentage: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomOutput {
+    pub text: String,
+    pub tooltip: Option<String>,
+    pub classes: Vec<String>,
+    pub percentage: Option<f64>,
+}
+
+impl CustomOutput {
+    /// Parses either a JSON object or plain text
+    fn parse(output: &str) -> Self {
+        let output = output.trim();
+        let Ok(json) = serde_json::from_str::<JsonOutput>(output) else {
+            return Self {
+                text: output.to_string(),
+                ..Default::default()
+            };
+        };
+
+        Self {
+            text: json.text,
+            tooltip: json.tooltip,
+            classes: match json.class {
+                Some(JsonClass::One(class)) => vec![class],
+                Some(JsonClass::Many(classes)) => classes,
+                None => Vec::new(),
+            },
+            percentage: json.percentage,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CustomMsg {
+    Poll {
+        generation: u64,
+    },
+    /// Polls right away, e.g. after a click command changed the state
+    Refresh,
+    Output(CustomOutput),
+    /// The polled command exited
+    PollFinished,
+    Click(u32),
+    Scroll(f64),
+}
+
+pub struct CustomModel {
+    config: CustomConfig,
+    output: CustomOutput,
+    /// Incremented on every scheduled poll to ignore outdated timers
+    generation: u64,
+    /// Set while the polled command runs, to not start it again if it takes longer than the
+    /// interval
+    polling: bool,
+}
+
+fn spawn(command: &str, flags: SubprocessFlags) -> Result<gio::Subprocess, glib::Error> {
+    gio::Subprocess::newv(
+        &[OsStr::new("sh"), OsStr::new("-c"), OsStr::new(command)],
+        flags,
+    )
+}
+
+async fn run(command: &str) -> Result<String, glib::Error> {
+    let process = spawn(command, SubprocessFlags::STDOUT_PIPE)?;
+    let (stdout, _) = process.communicate_utf8_future(None).await?;
+    Ok(stdout.map(String::from).unwrap_or_default())
+}
+
+/// Runs the command until it exits, sending every line of its output
+async fn stream(command: &str, sender: &ComponentSender<CustomModel>) -> Result<(), glib::Error> {
+    let process = spawn(command, SubprocessFlags::STDOUT_PIPE)?;
+    let stdout = gio::DataInputStream::new(&process.stdout_pipe().unwrap());
+
+    while let Some(line) = stdout.read_line_utf8_future(Priority::DEFAULT).await? {
+        sender.input(CustomMsg::Output(CustomOutput::parse(&line)));
+    }
+
+    process.wait_future().await
+}
+
+impl CustomModel {
+    fn format(&self) -> String {
+        let icon = self.config.icons.as_ref().and_then(|icons| {
+            let percentage = self.output.percentage?.clamp(0.0, 100.0);
+            let idx = (percentage / 100.0 * icons.len() as f64) as usize;
+            icons.get(idx.min(icons.len().checked_sub(1)?))
+        });
+
+        match icon {
+            Some(icon) => format!("{icon} {}", self.output.text),
+            None => self.output.text.clone(),
+        }
+    }
+
+    fn css_classes(&self) -> Vec<&str> {
+        let mut classes = vec!["widget", "custom"];
+        classes.extend(self.config.name.as_deref());
+        classes.extend(self.output.classes.iter().map(String::as_str));
+        classes
+    }
+
+    fn schedule_poll(&mut self, sender: &ComponentSender<Self>) {
+        let Some(interval) = self.config.interval else {
+            return;
+        };
+
+        self.generation += 1;
+
+        let generation = self.generation;
+        let sender = sender.clone();
+        // An interval of 0 would poll in a tight loop
+        let interval = Duration::from_secs(interval.max(1).into());
+        glib::timeout_add_local_once(interval, move || {
+            sender.input(CustomMsg::Poll { generation });
+        });
+    }
+
+    fn poll(&mut self, sender: &ComponentSender<Self>) {
+        self.schedule_poll(sender);
+
+        if self.polling {
+            return;
+        }
+        self.polling = true;
+
+        let exec = self.config.exec.clone();
+        let sender = sender.clone();
+        MainContext::default().spawn_local(async move {
+            match run(&exec).await {
+                Ok(output) => sender.input(CustomMsg::Output(CustomOutput::parse(&output))),
+                Err(err) => eprintln!("Failed to run {exec}: {err}"),
+            }
+            sender.input(CustomMsg::PollFinished);
+        });
+    }
+
+    fn run_handler(&self, command: Option<&String>, sender: &ComponentSender<Self>) {
+        let Some(command) = command.cloned() else {
+            return;
+        };
+
+        let sender = sender.clone();
+        let polled = self.config.interval.is_some();
+        MainContext::default().spawn_local(async move {
+            let result = match spawn(&command, SubprocessFlags::NONE) {
+                Ok(process) => process.wait_future().await,
+                Err(err) => Err(err),
+            };
+
+            match result {
+                // Show the effect of the command without waiting for the next poll
+                Ok(()) if polled => sender.input(CustomMsg::Refresh),
+                Ok(()) => {}
+                Err(err) => eprintln!("Failed to run {command}: {err}"),
+            }
+        });
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for CustomModel {
+    type Init = CustomConfig;
+
+    type Input = CustomMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        gtk::Label {
+            add_controller = gtk::GestureClick {
+                set_button: 0,
+                connect_released[sender] => move |gesture, _, _, _| {
+                    sender.input(CustomMsg::Click(gesture.current_button()));
+                },
+            },
+            add_controller = gtk::EventControllerScroll {
+                set_flags: gtk::EventControllerScrollFlags::VERTICAL
+                    | gtk::EventControllerScrollFlags::DISCRETE,
+                connect_scroll[sender] => move |_, _, dy| {
+                    sender.input(CustomMsg::Scroll(dy));
+                    gtk::glib::Propagation::Stop
+                },
+            },
+
+            #[watch]
+            set_css_classes: &model.css_classes(),
+            #[watch]
+            set_text: &model.format(),
+            #[watch]
+            set_tooltip_text: model.output.tooltip.as_deref(),
+            // Commands print nothing to hide the widget
+            #[watch]
+            set_visible: !model.output.text.is_empty(),
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = Self {
+            config,
+            output: CustomOutput::default(),
+            generation: 0,
+            polling: false,
+        };
+
+        if model.config.interval.is_some() {
+            model.poll(&sender);
+        } else {
+            let exec = model.config.exec.clone();
+            let sender = sender.clone();
+            MainContext::default().spawn_local(async move {
+                loop {
+                    if let Err(err) = stream(&exec, &sender).await {
+                        eprintln!("Failed to run {exec}: {err}");
+                    }
+
+                    timeout_future(RESTART_DELAY).await;
+                }
+            });
+        }
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            CustomMsg::Poll { generation } => {
+                if generation == self.generation {
+                    self.poll(&sender);
+                }
+            }
+            CustomMsg::Refresh => self.poll(&sender),
+            CustomMsg::Output(output) => self.output = output,
+            CustomMsg::PollFinished => self.polling = false,
+            CustomMsg::Click(button) => {
+                let command = match button {
+                    gtk::gdk::BUTTON_PRIMARY => self.config.on_click.as_ref(),
+                    gtk::gdk::BUTTON_MIDDLE => self.config.on_middle_click.as_ref(),
+                    gtk::gdk::BUTTON_SECONDARY => self.config.on_right_click.as_ref(),
+                    _ => None,
+                };
+                self.run_handler(command, &sender);
+            }
+            CustomMsg::Scroll(dy) => {
+                let command = if dy < 0.0 {
+                    self.config.on_scroll_up.as_ref()
+                } else {
+                    self.config.on_scroll_down.as_ref()
+                };
+                self.run_handler(command, &sender);
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_output() {
+        assert_eq!(
+            CustomOutput::parse("vpn up\n"),
+            CustomOutput {
+                text: "vpn up".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn json_output() {
+        assert_eq!(
+            CustomOutput::parse(
+                r#"{"text": "build", "tooltip": "main is green", "class": "ok", "percentage": 40}"#
+            ),
+            CustomOutput {
+                text: "build".to_string(),
+                tooltip: Some("main is green".to_string()),
+                classes: vec!["ok".to_string()],
+                percentage: Some(40.0),
+            }
+        );
+
+        assert_eq!(
+            CustomOutput::parse(r#"{"text": "on-call", "class": ["alert", "paged"]}"#).classes,
+            vec!["alert".to_string(), "paged".to_string()]
+        );
+    }
+
+    #[test]
+    fn invalid_json_is_text() {
+        assert_eq!(CustomOutput::parse("{not json}").text, "{not json}");
+    }
+}
@@ -0,0 +1,229 @@
+use relm4::{
+    gtk::{self, prelude::*},
+    prelude::*,
+};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use sysinfo::{Disk, DiskRefreshKind, Disks};
+
+use crate::{config::DiskConfig, units::format_size, widgets::network::NetworkModel};
+
+#[derive(Debug)]
+pub enum DiskMsg {
+    Update,
+}
+
+pub struct DiskModel {
+    mount_points: Vec<PathBuf>,
+    show_io: bool,
+    warning: Option<f32>,
+    critical: Option<f32>,
+
+    /// Reads the mounts from `/proc/mounts`, their usage with statvfs and the I/O counters of
+    /// their devices from `/proc/diskstats`
+    disks: Disks,
+    refreshed_at: Instant,
+    /// Time between the last two refreshes, over which the I/O counters were accumulated
+    elapsed: Duration,
+}
+
+fn usage(total: u64, available: u64) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    total.saturating_sub(available) as f32 / total as f32 * 100.0
+}
+
+/// Bytes per second
+fn rate(bytes: u64, elapsed: Duration) -> u64 {
+    if elapsed.is_zero() {
+        return 0;
+    }
+
+    (bytes as f64 / elapsed.as_secs_f64()) as u64
+}
+
+impl DiskModel {
+    fn refresh_kind() -> DiskRefreshKind {
+        DiskRefreshKind::nothing().with_storage().with_io_usage()
+    }
+
+    fn usage(disk: &Disk) -> f32 {
+        usage(disk.total_space(), disk.available_space())
+    }
+
+    /// Returns disks of the configured mount points, in the configured order
+    fn selected_disks(&self) -> impl Iterator<Item = &Disk> {
+        self.mount_points.iter().filter_map(|mount_point| {
+            self.disks
+                .list()
+                .iter()
+                .find(|disk| disk.mount_point() == mount_point)
+        })
+    }
+
+    fn max_usage(&self) -> Option<f32> {
+        self.selected_disks().map(Self::usage).reduce(f32::max)
+    }
+
+    fn is_warning(&self) -> bool {
+        self.warning
+            .zip(self.max_usage())
+            .is_some_and(|(warning, usage)| usage >= warning)
+            && !self.is_critical()
+    }
+
+    fn is_critical(&self) -> bool {
+        self.critical
+            .zip(self.max_usage())
+            .is_some_and(|(critical, usage)| usage >= critical)
+    }
+
+    fn format(&self) -> String {
+        let mut text = String::from("󰋊");
+
+        let single = self.mount_points.len() == 1;
+        for disk in self.selected_disks() {
+            if single {
+                let _ = write!(text, " {:.0}%", Self::usage(disk));
+            } else {
+                let _ = write!(
+                    text,
+                    " {} {:.0}%",
+                    disk.mount_point().display(),
+                    Self::usage(disk)
+                );
+            }
+        }
+
+        if self.show_io {
+            // Subvolumes of btrfs and bind mounts share the counters of their device
+            let (read, written) = self
+                .selected_disks()
+                .map(|disk| (disk.name(), disk.usage()))
+                .collect::<HashMap<_, _>>()
+                .into_values()
+                .fold((0, 0), |(read, written), usage| {
+                    (read + usage.read_bytes, written + usage.written_bytes)
+                });
+
+            let _ = write!(
+                text,
+                " 󰁆 {}/s 󰁞 {}/s",
+                NetworkModel::format_size(rate(read, self.elapsed)),
+                NetworkModel::format_size(rate(written, self.elapsed))
+            );
+        }
+
+        text
+    }
+
+    fn format_tooltip(&self) -> String {
+        let mut tooltip = String::new();
+
+        for disk in self.disks.list() {
+            let total = disk.total_space();
+            let used = total.saturating_sub(disk.available_space());
+            let _ = writeln!(
+                tooltip,
+                "{} ({}): {} / {} ({:.0}%)",
+                disk.mount_point().display(),
+                disk.file_system().to_string_lossy(),
+                format_size(used),
+                format_size(total),
+                Self::usage(disk)
+            );
+        }
+
+        tooltip.truncate(tooltip.trim_end_matches('\n').len());
+        tooltip
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for DiskModel {
+    type Init = DiskConfig;
+
+    type Input = DiskMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 4,
+
+            set_css_classes: &["widget", "disk"],
+
+            #[watch]
+            set_class_active: ("warning", model.is_warning()),
+            #[watch]
+            set_class_active: ("critical", model.is_critical()),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
+
+            append: label = &gtk::Label {
+                #[watch]
+                set_text: &model.format(),
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            mount_points: config
+                .mount_points
+                .unwrap_or_else(|| vec![PathBuf::from("/")]),
+            show_io: config.show_io.unwrap_or(false),
+            warning: config.warning,
+            critical: config.critical,
+
+            disks: Disks::new_with_refreshed_list_specifics(Self::refresh_kind()),
+            refreshed_at: Instant::now(),
+            elapsed: Duration::ZERO,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+        match message {
+            DiskMsg::Update => {
+                self.disks.refresh_specifics(true, Self::refresh_kind());
+                self.elapsed = self.refreshed_at.elapsed();
+                self.refreshed_at = Instant::now();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_usage() {
+        assert_eq!(usage(0, 0), 0.0);
+        assert_eq!(usage(1000, 1000), 0.0);
+        assert_eq!(usage(1000, 250), 75.0);
+        assert_eq!(usage(1000, 0), 100.0);
+    }
+
+    #[test]
+    fn io_rate() {
+        assert_eq!(rate(4096, Duration::ZERO), 0);
+        assert_eq!(rate(4096, Duration::from_secs(1)), 4096);
+        assert_eq!(rate(4096, Duration::from_secs(2)), 2048);
+        assert_eq!(rate(1000, Duration::from_millis(500)), 2000);
+    }
+}
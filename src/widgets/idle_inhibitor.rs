@@ -0,0 +1,187 @@
+use chrono::{DateTime, Local, TimeDelta};
+use relm4::{
+    gtk::{
+        self,
+        gio::{self, BusType},
+        glib::{self, MainContext},
+        prelude::*,
+    },
+    prelude::*,
+};
+use std::time::Duration;
+
+use crate::{
+    config::IdleInhibitorConfig,
+    idle_inhibitor::{IdleInhibitManager, IdleInhibitor, LogindInhibitor},
+};
+
+#[derive(Debug)]
+pub enum IdleInhibitorMsg {
+    Toggle,
+    Acquired(Result<LogindInhibitor, glib::Error>),
+    Timeout { generation: u64 },
+}
+
+pub struct IdleInhibitorModel {
+    /// Minutes until the inhibitor is released
+    timeout: Option<u32>,
+    /// `None` when the logind fallback is used
+    wayland: Option<IdleInhibitManager>,
+
+    inhibitor: Option<IdleInhibitor>,
+    /// Waiting for logind to return the inhibitor
+    pending: bool,
+    until: Option<DateTime<Local>>,
+    /// Incremented on every toggle to ignore timeouts of earlier inhibitors
+    generation: u64,
+}
+
+impl IdleInhibitorModel {
+    fn format_icon(&self) -> &'static str {
+        if self.inhibitor.is_some() {
+            "󰅶"
+        } else {
+            "󰛊"
+        }
+    }
+
+    fn format_tooltip(&self) -> String {
+        match (&self.inhibitor, self.until) {
+            (Some(_), Some(until)) => format!("Idle inhibited until {}", until.format("%H:%M")),
+            (Some(_), None) => "Idle inhibited".to_string(),
+            (None, _) => "Idle allowed".to_string(),
+        }
+    }
+
+    fn acquire(&mut self, sender: &ComponentSender<Self>, root: &gtk::Label) {
+        let surface = root.native().and_then(|native| native.surface());
+        let wayland = self
+            .wayland
+            .as_ref()
+            .zip(surface)
+            .and_then(|(manager, surface)| manager.inhibit(&surface));
+        if let Some(inhibitor) = wayland {
+            self.inhibitor = Some(IdleInhibitor::Wayland(inhibitor));
+            self.schedule_timeout(sender);
+            return;
+        }
+
+        self.pending = true;
+
+        let sender = sender.clone();
+        MainContext::default().spawn_local(async move {
+            let result = match gio::bus_get_future(BusType::System).await {
+                Ok(connection) => LogindInhibitor::new(&connection, "Enabled from the bar").await,
+                Err(err) => Err(err),
+            };
+            sender.input(IdleInhibitorMsg::Acquired(result));
+        });
+    }
+
+    fn schedule_timeout(&mut self, sender: &ComponentSender<Self>) {
+        let Some(timeout) = self.timeout else {
+            return;
+        };
+
+        let duration = Duration::from_secs(u64::from(timeout) * 60);
+        self.until = TimeDelta::from_std(duration)
+            .ok()
+            .map(|delta| Local::now() + delta);
+
+        let generation = self.generation;
+        let sender = sender.clone();
+        glib::timeout_add_local_once(duration, move || {
+            sender.input(IdleInhibitorMsg::Timeout { generation });
+        });
+    }
+
+    fn release(&mut self) {
+        self.inhibitor = None;
+        self.until = None;
+        self.generation += 1;
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for IdleInhibitorModel {
+    type Init = IdleInhibitorConfig;
+
+    type Input = IdleInhibitorMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        gtk::Label {
+            set_css_classes: &["widget", "idle-inhibitor"],
+
+            add_controller = gtk::GestureClick {
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(IdleInhibitorMsg::Toggle);
+                },
+            },
+
+            #[watch]
+            set_text: model.format_icon(),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
+            #[watch]
+            set_class_active: ("active", model.inhibitor.is_some()),
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            timeout: config.timeout,
+            wayland: IdleInhibitManager::new(&root.display()),
+
+            inhibitor: None,
+            pending: false,
+            until: None,
+            generation: 0,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            IdleInhibitorMsg::Toggle => {
+                if self.inhibitor.is_some() {
+                    self.release();
+                } else if !self.pending {
+                    self.acquire(&sender, root);
+                }
+            }
+            IdleInhibitorMsg::Acquired(result) => {
+                self.pending = false;
+                match result {
+                    Ok(inhibitor) => {
+                        self.inhibitor = Some(IdleInhibitor::Logind(inhibitor));
+                        self.schedule_timeout(&sender);
+                    }
+                    Err(err) => eprintln!("Failed to inhibit idle: {err}"),
+                }
+            }
+            IdleInhibitorMsg::Timeout { generation } => {
+                if generation == self.generation {
+                    self.release();
+                }
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
@@ -1,24 +1,50 @@
 use relm4::{
-    gtk::{self, prelude::*},
+    gtk::{self, glib::MainContext, prelude::*},
     prelude::*,
 };
 use std::collections::HashMap;
 
+use crate::hyprland::{self, Keyboard};
+
 #[derive(Debug)]
 pub enum LayoutMsg {
-    ActiveLayout { layout: String },
+    ActiveLayout { keyboard: String, layout: String },
+    NextLayout,
 }
 
 pub struct LayoutInit {
-    pub active_layout: String,
+    pub keyboard: Keyboard,
     pub layout_map: HashMap<String, String>,
 }
 
 pub struct LayoutModel {
+    keyboard_name: String,
+    /// Layouts and their variants from the keyboard's configuration
+    layouts: Vec<(String, String)>,
+
     layout: String,
     layout_map: HashMap<String, String>,
 }
 
+impl LayoutModel {
+    fn format_tooltip(&self) -> String {
+        let layouts = self
+            .layouts
+            .iter()
+            .map(|(layout, variant)| {
+                if variant.is_empty() {
+                    layout.clone()
+                } else {
+                    format!("{layout} ({variant})")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!("{}\n{}\n\n{layouts}", self.keyboard_name, self.layout)
+    }
+}
+
 #[relm4::component(pub)]
 impl SimpleComponent for LayoutModel {
     type Init = LayoutInit;
@@ -30,18 +56,42 @@ impl SimpleComponent for LayoutModel {
         gtk::Label {
             set_css_classes: &["widget", "layout"],
 
+            add_controller = gtk::GestureClick {
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(LayoutMsg::NextLayout);
+                },
+            },
+
             #[watch]
             set_text: model.layout_map.get(&model.layout).unwrap_or(&model.layout),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
         }
     }
 
     fn init(
         init: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        let keyboard = init.keyboard;
+
+        // Variants are listed in the same order as layouts, possibly with empty entries
+        let mut variants = keyboard.variant.split(',');
+        let layouts = keyboard
+            .layout
+            .split(',')
+            .map(|layout| {
+                let variant = variants.next().unwrap_or_default();
+                (layout.to_string(), variant.to_string())
+            })
+            .collect();
+
         let model = Self {
-            layout: init.active_layout,
+            keyboard_name: keyboard.name,
+            layouts,
+
+            layout: keyboard.active_keymap,
             layout_map: init.layout_map,
         };
 
@@ -52,7 +102,19 @@ impl SimpleComponent for LayoutModel {
 
     fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
         match message {
-            LayoutMsg::ActiveLayout { layout } => self.layout = layout,
+            // Ignore layout changes of other keyboards
+            LayoutMsg::ActiveLayout { keyboard, layout } if keyboard == self.keyboard_name => {
+                self.layout = layout;
+            }
+            LayoutMsg::ActiveLayout { .. } => {}
+            LayoutMsg::NextLayout => {
+                let keyboard_name = self.keyboard_name.clone();
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = hyprland::switch_xkb_layout(&keyboard_name, "next").await {
+                        eprintln!("{err}");
+                    }
+                });
+            }
         }
     }
 }
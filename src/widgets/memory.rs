@@ -2,20 +2,128 @@ use relm4::{
     gtk::{self, prelude::*},
     prelude::*,
 };
+use std::{cell::RefCell, fmt::Write, rc::Rc};
+use sysinfo::System;
+
+use crate::{
+    config::{MemoryConfig, MemoryFormat, MemoryUsage},
+    meminfo::get_cached_memory,
+    widgets::processes::{ProcessSort, ProcessesInit, ProcessesModel, ProcessesMsg},
+    zram::{get_zram_stats, ZramStats},
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryStats {
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+    pub total_swap: u64,
+    pub used_swap: u64,
+}
 
 #[derive(Debug)]
 pub enum MemoryMsg {
-    UpdateStats { used: u64, total: u64 },
+    UpdateStats(MemoryStats),
+    ShowProcesses,
+}
+
+pub struct MemoryInit {
+    pub config: MemoryConfig,
+    pub system: Rc<RefCell<System>>,
+    pub processes_count: usize,
 }
 
 pub struct MemoryModel {
-    used: u64,
-    total: u64,
+    format: MemoryFormat,
+    usage: MemoryUsage,
+    show_swap: bool,
+
+    stats: MemoryStats,
+    /// Page cache and buffers, not part of the stats of sysinfo
+    cached: u64,
+    /// Only the first failure to read the cached memory is logged
+    cached_failed: bool,
+    zram: ZramStats,
+
+    processes: Controller<ProcessesModel>,
+}
+
+impl MemoryModel {
+    fn format_gib(size: u64) -> String {
+        format!("{:.1}GiB", size as f64 / 1024.0 / 1024.0 / 1024.0)
+    }
+
+    fn percentage(value: u64, total: u64) -> f64 {
+        // Stats are zeroed until the first refresh
+        if total == 0 {
+            0.0
+        } else {
+            value as f64 / total as f64 * 100.0
+        }
+    }
+
+    fn format_value(&self, value: u64, total: u64) -> String {
+        match self.format {
+            MemoryFormat::Percentage => format!("{:.0}%", Self::percentage(value, total)),
+            MemoryFormat::Absolute => Self::format_gib(value),
+        }
+    }
+
+    fn format(&self) -> String {
+        let memory = match self.usage {
+            MemoryUsage::Used => self.stats.used,
+            MemoryUsage::Available => self.stats.available,
+        };
+
+        let mut text = format!("󰍛 {}", self.format_value(memory, self.stats.total));
+        if self.show_swap && self.stats.total_swap != 0 {
+            let swap = self.format_value(self.stats.used_swap, self.stats.total_swap);
+            let _ = write!(text, " 󰓡 {swap}");
+        }
+
+        text
+    }
+
+    fn format_tooltip(&self) -> String {
+        let stats = &self.stats;
+
+        let mut tooltip = format!(
+            "Used: {} / {}\nCached: {}\nAvailable: {}",
+            Self::format_gib(stats.used),
+            Self::format_gib(stats.total),
+            Self::format_gib(self.cached),
+            Self::format_gib(stats.available),
+        );
+
+        if stats.total_swap != 0 {
+            let _ = write!(
+                tooltip,
+                "\nSwap: {} / {}",
+                Self::format_gib(stats.used_swap),
+                Self::format_gib(stats.total_swap)
+            );
+        }
+
+        if self.zram.original != 0 {
+            let _ = write!(
+                tooltip,
+                "\nZram: {} ({} in RAM)",
+                Self::format_gib(self.zram.original),
+                Self::format_gib(self.zram.used)
+            );
+            if self.zram.compressed != 0 {
+                let ratio = self.zram.original as f64 / self.zram.compressed as f64;
+                let _ = write!(tooltip, "\nZram compression: {ratio:.1}x");
+            }
+        }
+
+        tooltip
+    }
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for MemoryModel {
-    type Init = ();
+    type Init = MemoryInit;
 
     type Input = MemoryMsg;
     type Output = ();
@@ -27,22 +135,49 @@ impl SimpleComponent for MemoryModel {
 
             set_css_classes: &["widget", "memory"],
 
+            add_controller = gtk::GestureClick {
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(MemoryMsg::ShowProcesses);
+                },
+            },
+
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
+
             append: label = &gtk::Label {
                 #[watch]
-                set_text: {
-                    let usage = model.used as f64 / model.total as f64 * 100.0;
-                    &format!("󰍛 {usage:.0}%")
-                }
+                set_text: &model.format(),
             }
         }
     }
 
     fn init(
-        _init: Self::Init,
+        init: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = Self { used: 0, total: 0 };
+        let processes = ProcessesModel::builder()
+            .launch(ProcessesInit {
+                system: init.system,
+                sort: ProcessSort::Memory,
+                count: init.processes_count,
+            })
+            .detach();
+        processes.widget().set_parent(&root);
+
+        let config = init.config;
+        let model = Self {
+            format: config.format.unwrap_or_default(),
+            usage: config.usage.unwrap_or_default(),
+            show_swap: config.show_swap.unwrap_or(false),
+
+            stats: MemoryStats::default(),
+            cached: 0,
+            cached_failed: false,
+            zram: ZramStats::default(),
+
+            processes,
+        };
 
         let widgets = view_output!();
 
@@ -51,10 +186,25 @@ impl SimpleComponent for MemoryModel {
 
     fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
         match message {
-            MemoryMsg::UpdateStats { used, total } => {
-                self.used = used;
-                self.total = total;
+            MemoryMsg::UpdateStats(stats) => {
+                self.stats = stats;
+                match get_cached_memory() {
+                    Ok(cached) => {
+                        self.cached = cached;
+                        self.cached_failed = false;
+                    }
+                    Err(err) => {
+                        if !self.cached_failed {
+                            eprintln!("Failed to read /proc/meminfo: {err}");
+                        }
+                        self.cached = 0;
+                        self.cached_failed = true;
+                    }
+                }
+                self.zram = get_zram_stats();
+                self.processes.emit(ProcessesMsg::Refresh);
             }
+            MemoryMsg::ShowProcesses => self.processes.widget().popup(),
         }
     }
 }
@@ -1,10 +1,23 @@
 pub mod active_window;
+pub mod backlight;
 pub mod battery;
+pub mod bluetooth;
+pub mod calendar;
 pub mod cpu;
+pub mod custom;
+pub mod disk;
+pub mod idle_inhibitor;
 pub mod image;
 pub mod layout;
 pub mod memory;
+pub mod mpris;
 pub mod network;
+pub mod notifications;
+pub mod power_profiles;
+pub mod processes;
 pub mod sound;
+pub mod submap;
+pub mod temperature;
 pub mod time;
+pub mod tray;
 pub mod workspaces;
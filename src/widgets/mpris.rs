@@ -0,0 +1,321 @@
+use relm4::{
+    gtk::{
+        self,
+        gio::{self, BusType},
+        glib::MainContext,
+        pango::EllipsizeMode,
+        prelude::*,
+    },
+    prelude::*,
+};
+
+use crate::{
+    config::MprisConfig,
+    mpris::{self, PlaybackStatus, Player, PlayerState},
+};
+
+const DEFAULT_MAX_LENGTH: i32 = 40;
+
+#[derive(Debug)]
+pub enum MprisMsg {
+    PlayerAppeared(String),
+    PlayerVanished(String),
+    PlayerReady(Player),
+    PlayerChanged,
+    PlayPause,
+    Next,
+    Previous,
+    ShowPlayers,
+    SelectPlayer(String),
+}
+
+pub struct MprisModel {
+    players: Vec<Player>,
+    /// Bus name of the player chosen from the popover
+    selected: Option<String>,
+
+    state: PlayerState,
+    popover: gtk::Popover,
+    player_list: gtk::Box,
+}
+
+impl MprisModel {
+    /// The selected player, otherwise the first playing one
+    fn current_player(&self) -> Option<&Player> {
+        self.selected
+            .as_ref()
+            .and_then(|selected| {
+                self.players
+                    .iter()
+                    .find(|player| player.bus_name == *selected)
+            })
+            .or_else(|| {
+                self.players
+                    .iter()
+                    .find(|player| player.state().status == PlaybackStatus::Playing)
+            })
+            .or_else(|| self.players.first())
+    }
+
+    fn format_icon(&self) -> &'static str {
+        match self.state.status {
+            PlaybackStatus::Playing => "󰐊",
+            PlaybackStatus::Paused => "󰏤",
+            PlaybackStatus::Stopped => "󰓛",
+        }
+    }
+
+    fn format_track(&self) -> String {
+        match (self.state.artist.as_str(), self.state.title.as_str()) {
+            ("", title) => title.to_string(),
+            (artist, "") => artist.to_string(),
+            (artist, title) => format!("{artist} - {title}"),
+        }
+    }
+
+    fn format_tooltip(&self) -> String {
+        let Some(player) = self.current_player() else {
+            return String::new();
+        };
+
+        let status = match self.state.status {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+            PlaybackStatus::Stopped => "Stopped",
+        };
+
+        format!("{}: {status}\n{}", player.name(), self.format_track())
+    }
+
+    fn call(&self, method: &'static str) {
+        let Some(player) = self.current_player().cloned() else {
+            return;
+        };
+
+        MainContext::default().spawn_local(async move {
+            if let Err(err) = player.call(method).await {
+                eprintln!("Failed to call {method} on {}: {err}", player.bus_name);
+            }
+        });
+    }
+
+    fn rebuild_player_list(&self, sender: &ComponentSender<Self>) {
+        while let Some(child) = self.player_list.first_child() {
+            self.player_list.remove(&child);
+        }
+
+        let current = self.current_player().map(|player| player.bus_name.clone());
+        for player in &self.players {
+            let button = gtk::Button::with_label(player.name());
+            button.set_css_classes(&["player"]);
+            if current.as_ref() == Some(&player.bus_name) {
+                button.add_css_class("selected");
+            }
+
+            button.connect_clicked({
+                let sender = sender.clone();
+                let bus_name = player.bus_name.clone();
+                move |_| sender.input(MprisMsg::SelectPlayer(bus_name.clone()))
+            });
+            self.player_list.append(&button);
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for MprisModel {
+    type Init = MprisConfig;
+
+    type Input = MprisMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        root = gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 4,
+
+            set_css_classes: &["widget", "mpris"],
+
+            #[watch]
+            set_visible: model.current_player().is_some(),
+            #[watch]
+            set_class_active: ("playing", model.state.status == PlaybackStatus::Playing),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
+
+            add_controller = gtk::GestureClick {
+                set_button: 0,
+                connect_released[sender] => move |gesture, _, _, _| {
+                    match gesture.current_button() {
+                        gtk::gdk::BUTTON_PRIMARY => sender.input(MprisMsg::PlayPause),
+                        gtk::gdk::BUTTON_SECONDARY => sender.input(MprisMsg::ShowPlayers),
+                        _ => {}
+                    }
+                },
+            },
+            add_controller = gtk::EventControllerScroll {
+                set_flags: gtk::EventControllerScrollFlags::VERTICAL
+                    | gtk::EventControllerScrollFlags::DISCRETE,
+                connect_scroll[sender] => move |_, _, dy| {
+                    if dy > 0.0 {
+                        sender.input(MprisMsg::Next);
+                    } else if dy < 0.0 {
+                        sender.input(MprisMsg::Previous);
+                    }
+                    gtk::glib::Propagation::Stop
+                },
+            },
+
+            gtk::Label {
+                #[watch]
+                set_text: model.format_icon(),
+            },
+            gtk::Label {
+                set_css_classes: &["track"],
+                set_ellipsize: EllipsizeMode::End,
+                set_max_width_chars: config.max_length.unwrap_or(DEFAULT_MAX_LENGTH),
+
+                #[watch]
+                set_text: &model.format_track(),
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let player_list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        let popover = gtk::Popover::new();
+        popover.set_css_classes(&["mpris-players"]);
+        popover.set_child(Some(&player_list));
+        popover.set_parent(&root);
+
+        let model = Self {
+            players: Vec::new(),
+            selected: None,
+
+            state: PlayerState::default(),
+            popover,
+            player_list,
+        };
+
+        // Uses DBUS_SESSION_BUS_ADDRESS, so it can be pointed at a separate dbus-daemon
+        MainContext::default().spawn_local({
+            let sender = sender.clone();
+            async move {
+                let connection = match gio::bus_get_future(BusType::Session).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        eprintln!("Failed to connect to the session bus: {err}");
+                        return;
+                    }
+                };
+
+                mpris::watch_players(&connection, {
+                    let sender = sender.clone();
+                    move |bus_name, appeared| {
+                        if appeared {
+                            sender.input(MprisMsg::PlayerAppeared(bus_name));
+                        } else {
+                            sender.input(MprisMsg::PlayerVanished(bus_name));
+                        }
+                    }
+                });
+
+                match mpris::list_players(&connection).await {
+                    Ok(bus_names) => {
+                        for bus_name in bus_names {
+                            sender.input(MprisMsg::PlayerAppeared(bus_name));
+                        }
+                    }
+                    Err(err) => eprintln!("Failed to list players: {err}"),
+                }
+            }
+        });
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            MprisMsg::PlayerAppeared(bus_name) => {
+                if self
+                    .players
+                    .iter()
+                    .any(|player| player.bus_name == bus_name)
+                {
+                    return;
+                }
+
+                let sender = sender.clone();
+                MainContext::default().spawn_local(async move {
+                    let connection = match gio::bus_get_future(BusType::Session).await {
+                        Ok(connection) => connection,
+                        Err(err) => {
+                            eprintln!("Failed to connect to the session bus: {err}");
+                            return;
+                        }
+                    };
+
+                    match Player::new(&connection, bus_name).await {
+                        Ok(player) => sender.input(MprisMsg::PlayerReady(player)),
+                        Err(err) => eprintln!("Failed to create player proxy: {err}"),
+                    }
+                });
+            }
+            MprisMsg::PlayerVanished(bus_name) => {
+                self.players.retain(|player| player.bus_name != bus_name);
+                if self.selected.as_ref() == Some(&bus_name) {
+                    self.selected = None;
+                }
+            }
+            MprisMsg::PlayerReady(player) => {
+                // The player may have vanished while the proxy was created
+                if !player.is_running()
+                    || self
+                        .players
+                        .iter()
+                        .any(|other| other.bus_name == player.bus_name)
+                {
+                    return;
+                }
+
+                player.connect_changed({
+                    let sender = sender.clone();
+                    move || sender.input(MprisMsg::PlayerChanged)
+                });
+                self.players.push(player);
+            }
+            MprisMsg::PlayerChanged => {}
+            MprisMsg::PlayPause => self.call("PlayPause"),
+            MprisMsg::Next => self.call("Next"),
+            MprisMsg::Previous => self.call("Previous"),
+            MprisMsg::ShowPlayers => {
+                if self.players.len() > 1 {
+                    self.rebuild_player_list(&sender);
+                    self.popover.popup();
+                }
+            }
+            MprisMsg::SelectPlayer(bus_name) => {
+                self.selected = Some(bus_name);
+                self.popover.popdown();
+            }
+        }
+
+        self.state = self.current_player().map(Player::state).unwrap_or_default();
+
+        self.update_view(widgets, sender);
+    }
+}
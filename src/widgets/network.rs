@@ -1,20 +1,72 @@
 use relm4::{
-    gtk::{self, prelude::*},
+    gtk::{
+        self,
+        gio::{self, BusType},
+        glib::{self, MainContext},
+        prelude::*,
+    },
     prelude::*,
 };
+use std::time::Duration;
+
+use crate::{
+    config::NetworkConfig,
+    network_manager::{
+        AccessPoint, Connectivity, NetworkManager, NetworkStatus, SavedConnection, TYPE_ETHERNET,
+        TYPE_WIRELESS,
+    },
+};
+
+/// NetworkManager signals come in bursts, e.g. for every access point found by a scan
+const REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+const WIFI_ICONS: [&str; 5] = ["󰤯", "󰤟", "󰤢", "󰤥", "󰤨"];
 
 #[derive(Debug)]
 pub enum NetworkMsg {
-    UpdateStats { transmitted: u64, received: u64 },
+    UpdateStats {
+        transmitted: u64,
+        received: u64,
+    },
+    Connected(NetworkManager),
+    Changed,
+    Refresh,
+    Status(NetworkStatus),
+    Networks {
+        access_points: Vec<AccessPoint>,
+        saved: Vec<SavedConnection>,
+    },
+    ShowNetworks,
+    /// Activates the saved connection
+    Activate(String),
+    /// Deactivates the active connection
+    Deactivate(String),
 }
 
 pub struct NetworkModel {
     transmitted: u64,
     received: u64,
+
+    network_manager: Option<NetworkManager>,
+    /// Only set with the NetworkManager integration
+    status: Option<NetworkStatus>,
+    access_points: Vec<AccessPoint>,
+    saved: Vec<SavedConnection>,
+    /// Set from the first change until the refresh, to only refresh once per burst
+    refresh_pending: bool,
+
+    popover: gtk::Popover,
+    wifi_list: gtk::Box,
+    vpn_list: gtk::Box,
+}
+
+fn wifi_icon(strength: u8) -> &'static str {
+    let idx = usize::from(strength.min(100)) * (WIFI_ICONS.len() - 1) / 100;
+    WIFI_ICONS[idx]
 }
 
 impl NetworkModel {
-    fn format_size(size: u64) -> String {
+    pub fn format_size(size: u64) -> String {
         if size < 1024 {
             format!("{size:.1}B")
         } else if size < 1024 * 1024 {
@@ -23,22 +75,209 @@ impl NetworkModel {
             format!("{:.1}MB", size as f64 / 1024.0 / 1024.0)
         }
     }
+
+    fn connectivity(&self) -> Connectivity {
+        self.status
+            .as_ref()
+            .map(|status| status.connectivity)
+            .unwrap_or_default()
+    }
+
+    fn vpn_active(&self) -> bool {
+        self.status
+            .as_ref()
+            .is_some_and(|status| status.active.iter().any(|active| active.vpn))
+    }
+
+    fn format_status(&self) -> String {
+        let Some(ref status) = self.status else {
+            return String::new();
+        };
+
+        let primary = status.active.iter().find(|active| !active.vpn);
+        let mut text = match primary {
+            Some(active) if active.kind == TYPE_WIRELESS => {
+                format!(
+                    "{} {}",
+                    wifi_icon(active.strength.unwrap_or(100)),
+                    active.id
+                )
+            }
+            Some(active) if active.kind == TYPE_ETHERNET => "󰈀".to_string(),
+            Some(active) => format!("󰛳 {}", active.id),
+            None => "󰤮".to_string(),
+        };
+
+        if self.vpn_active() {
+            text += " 󰦝";
+        }
+
+        text
+    }
+
+    fn format_tooltip(&self) -> Option<String> {
+        let status = self.status.as_ref()?;
+
+        let connectivity = match status.connectivity {
+            Connectivity::Unknown => "Unknown connectivity",
+            Connectivity::None => "Disconnected",
+            Connectivity::Portal => "Behind a captive portal",
+            Connectivity::Limited => "Limited connectivity",
+            Connectivity::Full => "Connected",
+        };
+
+        let mut lines = vec![connectivity.to_string()];
+        lines.extend(status.active.iter().map(|active| active.id.clone()));
+        Some(lines.join("\n"))
+    }
+
+    fn refresh(&self, sender: &ComponentSender<Self>, networks: bool) {
+        let Some(network_manager) = self.network_manager.clone() else {
+            return;
+        };
+
+        let sender = sender.clone();
+        MainContext::default().spawn_local(async move {
+            match network_manager.status().await {
+                Ok(status) => sender.input(NetworkMsg::Status(status)),
+                Err(err) => eprintln!("Failed to get the NetworkManager status: {err}"),
+            }
+
+            if !networks {
+                return;
+            }
+
+            let networks = async {
+                Ok::<_, glib::Error>((
+                    network_manager.access_points().await?,
+                    network_manager.saved_connections().await?,
+                ))
+            };
+            match networks.await {
+                Ok((access_points, saved)) => sender.input(NetworkMsg::Networks {
+                    access_points,
+                    saved,
+                }),
+                Err(err) => eprintln!("Failed to get networks: {err}"),
+            }
+        });
+    }
+
+    fn rebuild_lists(&self, sender: &ComponentSender<Self>) {
+        for list in [&self.wifi_list, &self.vpn_list] {
+            while let Some(child) = list.first_child() {
+                list.remove(&child);
+            }
+        }
+
+        let active = self
+            .status
+            .as_ref()
+            .map(|status| status.active.as_slice())
+            .unwrap_or_default();
+        let find_active =
+            |saved: &SavedConnection| active.iter().find(|active| active.connection == saved.path);
+
+        for access_point in &self.access_points {
+            let saved = self
+                .saved
+                .iter()
+                .find(|saved| saved.ssid.as_ref() == Some(&access_point.ssid));
+            let is_active = saved.and_then(find_active).is_some();
+
+            let mut text = format!("{} {}", wifi_icon(access_point.strength), access_point.ssid);
+            if access_point.secured {
+                text += " 󰌾";
+            }
+
+            let button = gtk::Button::with_label(&text);
+            button.set_css_classes(&["network"]);
+            if is_active {
+                button.add_css_class("active");
+            }
+            // Only saved networks can be activated without asking for a password
+            button.set_sensitive(saved.is_some() && !is_active);
+
+            if let Some(saved) = saved {
+                button.connect_clicked({
+                    let sender = sender.clone();
+                    let path = saved.path.clone();
+                    move |_| sender.input(NetworkMsg::Activate(path.clone()))
+                });
+            }
+            self.wifi_list.append(&button);
+        }
+
+        if self.access_points.is_empty() {
+            let empty = gtk::Label::new(Some("No Wi-Fi networks"));
+            empty.set_css_classes(&["empty"]);
+            self.wifi_list.append(&empty);
+        }
+
+        for saved in self.saved.iter().filter(|saved| saved.is_vpn()) {
+            let active = find_active(saved);
+
+            let button = gtk::Button::with_label(&format!("󰦝 {}", saved.id));
+            button.set_css_classes(&["vpn"]);
+            if active.is_some() {
+                button.add_css_class("active");
+            }
+
+            button.connect_clicked({
+                let sender = sender.clone();
+                let active = active.map(|active| active.path.clone());
+                let path = saved.path.clone();
+                move |_| match active {
+                    Some(ref active) => sender.input(NetworkMsg::Deactivate(active.clone())),
+                    None => sender.input(NetworkMsg::Activate(path.clone())),
+                }
+            });
+            self.vpn_list.append(&button);
+        }
+
+        self.vpn_list
+            .set_visible(self.vpn_list.first_child().is_some());
+    }
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for NetworkModel {
-    type Init = ();
+    type Init = NetworkConfig;
 
     type Input = NetworkMsg;
     type Output = ();
 
     view! {
-        gtk::Box {
+        root = gtk::Box {
             set_orientation: gtk::Orientation::Horizontal,
             set_spacing: 4,
 
             set_css_classes: &["widget", "network"],
 
+            add_controller = gtk::GestureClick {
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(NetworkMsg::ShowNetworks);
+                },
+            },
+
+            #[watch]
+            set_tooltip_text: model.format_tooltip().as_deref(),
+            #[watch]
+            set_class_active: ("disconnected", model.connectivity() == Connectivity::None),
+            #[watch]
+            set_class_active: ("portal", model.connectivity() == Connectivity::Portal),
+            #[watch]
+            set_class_active: ("limited", model.connectivity() == Connectivity::Limited),
+            #[watch]
+            set_class_active: ("vpn", model.vpn_active()),
+
+            append: status_label = &gtk::Label {
+                #[watch]
+                set_text: &model.format_status(),
+                #[watch]
+                set_visible: model.status.is_some(),
+            },
+
             append: label = &gtk::Label {
                 #[watch]
                 set_text: {
@@ -52,21 +291,63 @@ impl SimpleComponent for NetworkModel {
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        let wifi_list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        let wifi_scroll = gtk::ScrolledWindow::new();
+        wifi_scroll.set_hscrollbar_policy(gtk::PolicyType::Never);
+        wifi_scroll.set_propagate_natural_height(true);
+        wifi_scroll.set_max_content_height(400);
+        wifi_scroll.set_child(Some(&wifi_list));
+
+        let vpn_list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+
+        let popover_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+        popover_box.append(&wifi_scroll);
+        popover_box.append(&vpn_list);
+
+        let popover = gtk::Popover::new();
+        popover.set_css_classes(&["networks"]);
+        popover.set_child(Some(&popover_box));
+        popover.set_parent(&root);
+
         let model = Self {
             transmitted: 0,
             received: 0,
+
+            network_manager: None,
+            status: None,
+            access_points: Vec::new(),
+            saved: Vec::new(),
+            refresh_pending: false,
+
+            popover,
+            wifi_list,
+            vpn_list,
         };
 
+        if config.network_manager.unwrap_or_default() {
+            MainContext::default().spawn_local({
+                let sender = sender.clone();
+                async move {
+                    match gio::bus_get_future(BusType::System).await {
+                        Ok(connection) => {
+                            sender.input(NetworkMsg::Connected(NetworkManager::new(connection)))
+                        }
+                        Err(err) => eprintln!("Failed to connect to the system bus: {err}"),
+                    }
+                }
+            });
+        }
+
         let widgets = view_output!();
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
             NetworkMsg::UpdateStats {
                 transmitted,
@@ -75,6 +356,80 @@ impl SimpleComponent for NetworkModel {
                 self.transmitted = transmitted;
                 self.received = received;
             }
+            NetworkMsg::Connected(network_manager) => {
+                network_manager.connect_changed({
+                    let sender = sender.clone();
+                    move || sender.input(NetworkMsg::Changed)
+                });
+                self.network_manager = Some(network_manager);
+                self.refresh(&sender, false);
+            }
+            // Refreshes at most once per delay, even if the changes keep coming
+            NetworkMsg::Changed if !self.refresh_pending => {
+                self.refresh_pending = true;
+
+                let sender = sender.clone();
+                glib::timeout_add_local_once(REFRESH_DELAY, move || {
+                    sender.input(NetworkMsg::Refresh);
+                });
+            }
+            NetworkMsg::Changed => {}
+            NetworkMsg::Refresh => {
+                self.refresh_pending = false;
+                self.refresh(&sender, self.popover.is_visible());
+            }
+            NetworkMsg::Status(status) => {
+                self.status = Some(status);
+                if self.popover.is_visible() {
+                    self.rebuild_lists(&sender);
+                }
+            }
+            NetworkMsg::Networks {
+                access_points,
+                saved,
+            } => {
+                self.access_points = access_points;
+                self.saved = saved;
+                if self.popover.is_visible() {
+                    self.rebuild_lists(&sender);
+                }
+            }
+            NetworkMsg::ShowNetworks => {
+                let Some(network_manager) = self.network_manager.clone() else {
+                    return;
+                };
+
+                self.popover.popup();
+                self.refresh(&sender, true);
+
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = network_manager.request_scan().await {
+                        eprintln!("Failed to scan for Wi-Fi networks: {err}");
+                    }
+                });
+            }
+            NetworkMsg::Activate(path) => {
+                let Some(network_manager) = self.network_manager.clone() else {
+                    return;
+                };
+
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = network_manager.activate(&path).await {
+                        eprintln!("Failed to activate {path}: {err}");
+                    }
+                });
+            }
+            NetworkMsg::Deactivate(path) => {
+                let Some(network_manager) = self.network_manager.clone() else {
+                    return;
+                };
+
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = network_manager.deactivate(&path).await {
+                        eprintln!("Failed to deactivate {path}: {err}");
+                    }
+                });
+            }
         }
     }
 }
@@ -0,0 +1,513 @@
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::{
+    gtk::{
+        self,
+        gio::{self, BusType},
+        glib::{self, MainContext},
+        pango::WrapMode,
+        prelude::*,
+    },
+    prelude::*,
+};
+use std::{collections::HashSet, path::Path};
+
+use crate::{
+    config::NotificationsConfig,
+    notifications::{CloseReason, Notification, NotificationServer, ServerEvent, Urgency},
+};
+
+const DEFAULT_TIMEOUT: u32 = 5000;
+const DEFAULT_MAX_HISTORY: usize = 100;
+const DEFAULT_ACTION: &str = "default";
+
+#[derive(Debug)]
+pub enum NotificationsMsg {
+    Started(NotificationServer),
+    Server(ServerEvent),
+    Expire {
+        id: u32,
+        generation: u64,
+    },
+    /// Closes the popup, the notification stays in the history
+    ClosePopup(u32),
+    /// Removes the notification from the history
+    Remove(u32),
+    InvokeAction {
+        id: u32,
+        key: String,
+    },
+    ToggleDnd,
+    ShowHistory,
+    ClearHistory,
+}
+
+struct Popup {
+    id: u32,
+    /// Replacing a notification restarts its timeout
+    generation: u64,
+    widget: gtk::Box,
+}
+
+pub struct NotificationsModel {
+    default_timeout: u32,
+    max_history: usize,
+
+    server: Option<NotificationServer>,
+    /// Newest first
+    history: Vec<Notification>,
+    /// Notifications that weren't closed yet, `NotificationClosed` is only sent once per ID
+    open: HashSet<u32>,
+    unread: usize,
+    dnd: bool,
+
+    popups: Vec<Popup>,
+    generation: u64,
+    popup_window: gtk::Window,
+    popup_list: gtk::Box,
+
+    history_popover: gtk::Popover,
+    history_list: gtk::Box,
+}
+
+fn notification_icon(app_icon: &str) -> Option<gio::Icon> {
+    let app_icon = app_icon.strip_prefix("file://").unwrap_or(app_icon);
+    if app_icon.is_empty() {
+        None
+    } else if Path::new(app_icon).is_absolute() {
+        Some(gio::FileIcon::new(&gio::File::for_path(app_icon)).upcast())
+    } else {
+        Some(gio::ThemedIcon::new(app_icon).upcast())
+    }
+}
+
+fn build_notification(
+    notification: &Notification,
+    close_msg: fn(u32) -> NotificationsMsg,
+    sender: &ComponentSender<NotificationsModel>,
+) -> gtk::Box {
+    let id = notification.id;
+
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    container.set_css_classes(&[
+        "notification",
+        match notification.urgency {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        },
+    ]);
+
+    let header = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    if let Some(icon) = notification_icon(&notification.app_icon) {
+        let image = gtk::Image::from_gicon(&icon);
+        image.set_css_classes(&["icon"]);
+        header.append(&image);
+    }
+
+    let summary = gtk::Label::new(Some(&notification.summary));
+    summary.set_css_classes(&["summary"]);
+    summary.set_hexpand(true);
+    summary.set_xalign(0.0);
+    summary.set_wrap(true);
+    summary.set_wrap_mode(WrapMode::WordChar);
+    header.append(&summary);
+
+    let close = gtk::Button::with_label("󰅖");
+    close.set_css_classes(&["close"]);
+    close.connect_clicked({
+        let sender = sender.clone();
+        move |_| sender.input(close_msg(id))
+    });
+    header.append(&close);
+    container.append(&header);
+
+    if !notification.body.is_empty() {
+        let body = gtk::Label::new(Some(&notification.body));
+        body.set_css_classes(&["body"]);
+        body.set_xalign(0.0);
+        body.set_wrap(true);
+        body.set_wrap_mode(WrapMode::WordChar);
+        body.set_max_width_chars(40);
+        container.append(&body);
+    }
+
+    let app = gtk::Label::new(Some(&format!(
+        "{} · {}",
+        notification.app_name,
+        notification.time.format("%H:%M")
+    )));
+    app.set_css_classes(&["app"]);
+    app.set_xalign(0.0);
+    container.append(&app);
+
+    let actions = notification
+        .actions
+        .iter()
+        .filter(|action| action.key != DEFAULT_ACTION)
+        .collect::<Vec<_>>();
+    if !actions.is_empty() {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        row.set_css_classes(&["actions"]);
+        row.set_homogeneous(true);
+
+        for action in actions {
+            let button = gtk::Button::with_label(&action.label);
+            button.connect_clicked({
+                let sender = sender.clone();
+                let key = action.key.clone();
+                move |_| {
+                    sender.input(NotificationsMsg::InvokeAction {
+                        id,
+                        key: key.clone(),
+                    });
+                }
+            });
+            row.append(&button);
+        }
+        container.append(&row);
+    }
+
+    // Clicking the notification itself invokes the default action
+    if notification
+        .actions
+        .iter()
+        .any(|action| action.key == DEFAULT_ACTION)
+    {
+        let click = gtk::GestureClick::new();
+        click.connect_released({
+            let sender = sender.clone();
+            move |_, _, _, _| {
+                sender.input(NotificationsMsg::InvokeAction {
+                    id,
+                    key: DEFAULT_ACTION.to_string(),
+                });
+            }
+        });
+        container.add_controller(click);
+    }
+
+    container
+}
+
+impl NotificationsModel {
+    fn format_icon(&self) -> &'static str {
+        if self.dnd {
+            "󰂛"
+        } else if self.unread != 0 {
+            "󰂞"
+        } else {
+            "󰂚"
+        }
+    }
+
+    fn format(&self) -> String {
+        if self.unread == 0 {
+            self.format_icon().to_string()
+        } else {
+            format!("{} {}", self.format_icon(), self.unread)
+        }
+    }
+
+    /// Returns whether there was a popup
+    fn close_popup(&mut self, id: u32) -> bool {
+        let Some(idx) = self.popups.iter().position(|popup| popup.id == id) else {
+            return false;
+        };
+
+        let popup = self.popups.remove(idx);
+        self.popup_list.remove(&popup.widget);
+        self.popup_window.set_visible(!self.popups.is_empty());
+        true
+    }
+
+    /// Sends `NotificationClosed` if the notification is still open
+    fn closed(&mut self, id: u32, reason: CloseReason) {
+        if !self.open.remove(&id) {
+            return;
+        }
+
+        if let Some(ref server) = self.server {
+            server.closed(id, reason);
+        }
+    }
+
+    fn show_popup(&mut self, notification: &Notification, sender: &ComponentSender<Self>) {
+        let widget = build_notification(notification, NotificationsMsg::ClosePopup, sender);
+        self.generation += 1;
+        let generation = self.generation;
+
+        // Replaced notifications keep their position
+        if let Some(popup) = self
+            .popups
+            .iter_mut()
+            .find(|popup| popup.id == notification.id)
+        {
+            self.popup_list
+                .insert_child_after(&widget, Some(&popup.widget));
+            self.popup_list.remove(&popup.widget);
+            popup.widget = widget;
+            popup.generation = generation;
+        } else {
+            self.popup_list.prepend(&widget);
+            self.popups.push(Popup {
+                id: notification.id,
+                generation,
+                widget,
+            });
+        }
+        self.popup_window.set_visible(true);
+
+        let timeout = match (notification.expire_timeout, notification.urgency) {
+            (0, _) | (-1, Urgency::Critical) => return,
+            (-1, _) => self.default_timeout,
+            (timeout, _) => timeout.unsigned_abs(),
+        };
+
+        let sender = sender.clone();
+        let id = notification.id;
+        glib::timeout_add_local_once(
+            std::time::Duration::from_millis(timeout.into()),
+            move || sender.input(NotificationsMsg::Expire { id, generation }),
+        );
+    }
+
+    fn notify(&mut self, notification: Notification, sender: &ComponentSender<Self>) {
+        if let Some(idx) = self
+            .history
+            .iter()
+            .position(|other| other.id == notification.id)
+        {
+            self.history.remove(idx);
+        } else {
+            self.unread += 1;
+        }
+
+        self.open.insert(notification.id);
+        if !self.dnd || notification.urgency == Urgency::Critical {
+            self.show_popup(&notification, sender);
+        }
+
+        self.history.insert(0, notification);
+        // Notifications dropped from the history can't be closed from the bar anymore
+        for notification in self
+            .history
+            .drain(self.max_history.min(self.history.len())..)
+        {
+            self.open.remove(&notification.id);
+        }
+    }
+
+    fn rebuild_history(&self, sender: &ComponentSender<Self>) {
+        while let Some(child) = self.history_list.first_child() {
+            self.history_list.remove(&child);
+        }
+
+        if self.history.is_empty() {
+            let empty = gtk::Label::new(Some("No notifications"));
+            empty.set_css_classes(&["empty"]);
+            self.history_list.append(&empty);
+            return;
+        }
+
+        for notification in &self.history {
+            self.history_list.append(&build_notification(
+                notification,
+                NotificationsMsg::Remove,
+                sender,
+            ));
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for NotificationsModel {
+    type Init = NotificationsConfig;
+
+    type Input = NotificationsMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        root = gtk::Label {
+            set_css_classes: &["widget", "notifications"],
+
+            add_controller = gtk::GestureClick {
+                set_button: 0,
+                connect_released[sender] => move |gesture, _, _, _| {
+                    match gesture.current_button() {
+                        gtk::gdk::BUTTON_PRIMARY => sender.input(NotificationsMsg::ShowHistory),
+                        gtk::gdk::BUTTON_SECONDARY => sender.input(NotificationsMsg::ToggleDnd),
+                        _ => {}
+                    }
+                },
+            },
+
+            #[watch]
+            set_text: &model.format(),
+            #[watch]
+            set_class_active: ("dnd", model.dnd),
+            #[watch]
+            set_class_active: ("unread", model.unread != 0),
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popup_list = gtk::Box::new(gtk::Orientation::Vertical, 8);
+        let popup_window = gtk::Window::new();
+        popup_window.set_application(Some(&relm4::main_application()));
+        popup_window.init_layer_shell();
+        popup_window.set_layer(Layer::Overlay);
+        popup_window.set_namespace(Some("crabbar-notifications"));
+        popup_window.set_anchor(Edge::Top, true);
+        popup_window.set_anchor(Edge::Right, true);
+        popup_window.set_css_classes(&["notification-popups"]);
+        popup_window.set_child(Some(&popup_list));
+
+        let history_list = gtk::Box::new(gtk::Orientation::Vertical, 8);
+        let clear = gtk::Button::with_label("Clear");
+        clear.set_css_classes(&["clear"]);
+        clear.connect_clicked({
+            let sender = sender.clone();
+            move |_| sender.input(NotificationsMsg::ClearHistory)
+        });
+
+        let history_scroll = gtk::ScrolledWindow::new();
+        history_scroll.set_hscrollbar_policy(gtk::PolicyType::Never);
+        history_scroll.set_propagate_natural_height(true);
+        history_scroll.set_max_content_height(600);
+        history_scroll.set_child(Some(&history_list));
+
+        let history_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+        history_box.append(&history_scroll);
+        history_box.append(&clear);
+
+        let history_popover = gtk::Popover::new();
+        history_popover.set_css_classes(&["notification-history"]);
+        history_popover.set_child(Some(&history_box));
+        history_popover.set_parent(&root);
+
+        let model = Self {
+            default_timeout: config.default_timeout.unwrap_or(DEFAULT_TIMEOUT),
+            max_history: config.max_history.unwrap_or(DEFAULT_MAX_HISTORY),
+
+            server: None,
+            history: Vec::new(),
+            open: HashSet::new(),
+            unread: 0,
+            dnd: false,
+
+            popups: Vec::new(),
+            generation: 0,
+            popup_window,
+            popup_list,
+
+            history_popover,
+            history_list,
+        };
+
+        // Uses DBUS_SESSION_BUS_ADDRESS, so `notify-send` can be tested against a private bus
+        MainContext::default().spawn_local({
+            let sender = sender.clone();
+            async move {
+                let connection = match gio::bus_get_future(BusType::Session).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        eprintln!("Failed to connect to the session bus: {err}");
+                        return;
+                    }
+                };
+
+                let server = NotificationServer::start(&connection, {
+                    let sender = sender.clone();
+                    move |event| sender.input(NotificationsMsg::Server(event))
+                });
+                match server {
+                    Ok(server) => sender.input(NotificationsMsg::Started(server)),
+                    Err(err) => eprintln!("Failed to start the notification server: {err}"),
+                }
+            }
+        });
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            NotificationsMsg::Started(server) => self.server = Some(server),
+            NotificationsMsg::Server(ServerEvent::Notify(notification)) => {
+                self.notify(notification, &sender);
+            }
+            NotificationsMsg::Server(ServerEvent::Close(id)) => {
+                self.close_popup(id);
+                self.history.retain(|notification| notification.id != id);
+                self.closed(id, CloseReason::Closed);
+            }
+            NotificationsMsg::Expire { id, generation } => {
+                if self
+                    .popups
+                    .iter()
+                    .any(|popup| popup.id == id && popup.generation == generation)
+                {
+                    self.close_popup(id);
+                    self.closed(id, CloseReason::Expired);
+                }
+            }
+            NotificationsMsg::ClosePopup(id) => {
+                self.close_popup(id);
+                self.closed(id, CloseReason::Dismissed);
+            }
+            NotificationsMsg::Remove(id) => {
+                self.close_popup(id);
+                self.closed(id, CloseReason::Dismissed);
+                self.history.retain(|notification| notification.id != id);
+                self.rebuild_history(&sender);
+            }
+            NotificationsMsg::InvokeAction { id, key } => {
+                if let Some(ref server) = self.server {
+                    server.action_invoked(id, &key);
+                }
+                if self.close_popup(id) {
+                    self.closed(id, CloseReason::Dismissed);
+                }
+                self.history_popover.popdown();
+            }
+            NotificationsMsg::ToggleDnd => self.dnd = !self.dnd,
+            NotificationsMsg::ShowHistory => {
+                self.unread = 0;
+                self.rebuild_history(&sender);
+                self.history_popover.popup();
+            }
+            NotificationsMsg::ClearHistory => {
+                let ids = self
+                    .popups
+                    .iter()
+                    .map(|popup| popup.id)
+                    .chain(self.history.iter().map(|notification| notification.id))
+                    .collect::<Vec<_>>();
+                for id in ids {
+                    self.close_popup(id);
+                    self.closed(id, CloseReason::Dismissed);
+                }
+
+                self.history.clear();
+                self.unread = 0;
+                self.rebuild_history(&sender);
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
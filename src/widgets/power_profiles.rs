@@ -0,0 +1,162 @@
+use relm4::{
+    gtk::{
+        self,
+        gio::{self, BusType},
+        glib::MainContext,
+        prelude::*,
+    },
+    prelude::*,
+};
+
+use crate::{
+    config::PowerProfilesConfig,
+    power_profiles::{cycle_profile, PowerProfiles},
+};
+
+#[derive(Debug)]
+pub enum PowerProfilesMsg {
+    Connected(PowerProfiles),
+    Changed,
+    Cycle { forward: bool },
+}
+
+pub struct PowerProfilesModel {
+    power_profiles: Option<PowerProfiles>,
+    active_profile: String,
+    profiles: Vec<String>,
+}
+
+impl PowerProfilesModel {
+    fn format_icon(&self) -> &'static str {
+        match self.active_profile.as_str() {
+            "power-saver" => "󰾆",
+            "performance" => "󰓅",
+            _ => "󰾅",
+        }
+    }
+
+    fn format_tooltip(&self) -> String {
+        format!("Power profile: {}", self.active_profile)
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for PowerProfilesModel {
+    type Init = PowerProfilesConfig;
+
+    type Input = PowerProfilesMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        gtk::Label {
+            set_css_classes: &["widget", "power-profiles"],
+
+            add_controller = gtk::GestureClick {
+                set_button: 0,
+                connect_released[sender] => move |gesture, _, _, _| {
+                    match gesture.current_button() {
+                        gtk::gdk::BUTTON_PRIMARY => {
+                            sender.input(PowerProfilesMsg::Cycle { forward: true });
+                        }
+                        gtk::gdk::BUTTON_SECONDARY => {
+                            sender.input(PowerProfilesMsg::Cycle { forward: false });
+                        }
+                        _ => {}
+                    }
+                },
+            },
+
+            #[watch]
+            set_text: model.format_icon(),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
+            #[watch]
+            set_class_active: ("power-saver", model.active_profile == "power-saver"),
+            #[watch]
+            set_class_active: ("performance", model.active_profile == "performance"),
+            // Hidden without power-profiles-daemon
+            #[watch]
+            set_visible: !model.active_profile.is_empty(),
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            power_profiles: None,
+            active_profile: String::new(),
+            profiles: Vec::new(),
+        };
+
+        MainContext::default().spawn_local({
+            let sender = sender.clone();
+            async move {
+                let connection = match gio::bus_get_future(BusType::System).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        eprintln!("Failed to connect to the system bus: {err}");
+                        return;
+                    }
+                };
+
+                match PowerProfiles::new(&connection).await {
+                    Ok(power_profiles) => {
+                        sender.input(PowerProfilesMsg::Connected(power_profiles));
+                    }
+                    Err(err) => eprintln!("Failed to connect to power-profiles-daemon: {err}"),
+                }
+            }
+        });
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            PowerProfilesMsg::Connected(power_profiles) => {
+                power_profiles.connect_changed({
+                    let sender = sender.clone();
+                    move || sender.input(PowerProfilesMsg::Changed)
+                });
+                self.power_profiles = Some(power_profiles);
+                sender.input(PowerProfilesMsg::Changed);
+            }
+            PowerProfilesMsg::Changed => {
+                if let Some(ref power_profiles) = self.power_profiles {
+                    self.active_profile = power_profiles.active_profile();
+                    self.profiles = power_profiles.profiles();
+                }
+            }
+            PowerProfilesMsg::Cycle { forward } => {
+                let (Some(power_profiles), Some(profile)) = (
+                    self.power_profiles.clone(),
+                    cycle_profile(&self.profiles, &self.active_profile, forward),
+                ) else {
+                    return;
+                };
+
+                let profile = profile.to_string();
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = power_profiles.set_active_profile(&profile).await {
+                        eprintln!("Failed to set the power profile to {profile}: {err}");
+                    }
+                });
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
@@ -0,0 +1,228 @@
+use relm4::{
+    gtk::{self, glib, pango::EllipsizeMode, prelude::*},
+    prelude::*,
+};
+use std::{cell::RefCell, cmp::Reverse, collections::HashSet, rc::Rc};
+use sysinfo::{
+    Pid, ProcessRefreshKind, ProcessesToUpdate, Signal, System, MINIMUM_CPU_UPDATE_INTERVAL,
+};
+
+use crate::units::format_size;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ProcessSort {
+    Cpu,
+    Memory,
+}
+
+pub struct ProcessesInit {
+    pub system: Rc<RefCell<System>>,
+    pub sort: ProcessSort,
+    pub count: usize,
+}
+
+#[derive(Debug)]
+pub enum ProcessesMsg {
+    Show,
+    Refresh,
+    Terminate(Pid),
+    Kill(Pid),
+}
+
+pub struct ProcessesModel {
+    system: Rc<RefCell<System>>,
+    sort: ProcessSort,
+    count: usize,
+
+    /// Processes that were sent SIGTERM, clicking them again sends SIGKILL
+    terminated: HashSet<Pid>,
+}
+
+impl ProcessesModel {
+    /// CPU usage is computed from the difference with the previous refresh, so processes have 0%
+    /// until they have been refreshed twice
+    fn refresh_processes(system: &mut System) {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+    }
+
+    fn refresh(&mut self, list: &gtk::Box, sender: &ComponentSender<Self>) {
+        let mut system = self.system.borrow_mut();
+        Self::refresh_processes(&mut system);
+
+        let processes = system.processes();
+        self.terminated.retain(|pid| processes.contains_key(pid));
+
+        let mut processes = processes.values().collect::<Vec<_>>();
+        match self.sort {
+            ProcessSort::Cpu => {
+                processes.sort_unstable_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()));
+            }
+            ProcessSort::Memory => {
+                processes.sort_unstable_by_key(|process| Reverse(process.memory()))
+            }
+        }
+
+        while let Some(child) = list.first_child() {
+            list.remove(&child);
+        }
+
+        for process in processes.into_iter().take(self.count) {
+            let pid = process.pid();
+
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+            row.set_css_classes(&["process"]);
+
+            let name = gtk::Label::new(Some(&process.name().to_string_lossy()));
+            name.set_css_classes(&["name"]);
+            name.set_hexpand(true);
+            name.set_xalign(0.0);
+            name.set_ellipsize(EllipsizeMode::End);
+            name.set_max_width_chars(24);
+            row.append(&name);
+
+            let pid_label = gtk::Label::new(Some(&pid.to_string()));
+            pid_label.set_css_classes(&["pid"]);
+            row.append(&pid_label);
+
+            let cpu = gtk::Label::new(Some(&format!("{:.1}%", process.cpu_usage())));
+            cpu.set_css_classes(&["cpu"]);
+            cpu.set_width_chars(6);
+            cpu.set_xalign(1.0);
+            row.append(&cpu);
+
+            let memory = gtk::Label::new(Some(&format_size(process.memory())));
+            memory.set_css_classes(&["memory"]);
+            memory.set_width_chars(9);
+            memory.set_xalign(1.0);
+            row.append(&memory);
+
+            let button = if self.terminated.contains(&pid) {
+                let button = gtk::Button::with_label("Kill");
+                button.set_css_classes(&["kill", "destructive-action"]);
+                button.set_tooltip_text(Some("Send SIGKILL"));
+                button.connect_clicked({
+                    let sender = sender.clone();
+                    move |_| sender.input(ProcessesMsg::Kill(pid))
+                });
+                button
+            } else {
+                let button = gtk::Button::with_label("Terminate");
+                button.set_css_classes(&["terminate"]);
+                button.set_tooltip_text(Some("Send SIGTERM"));
+                button.connect_clicked({
+                    let sender = sender.clone();
+                    move |_| sender.input(ProcessesMsg::Terminate(pid))
+                });
+                button
+            };
+            row.append(&button);
+
+            list.append(&row);
+        }
+    }
+
+    fn signal(&self, pid: Pid, signal: Signal) -> bool {
+        let system = self.system.borrow();
+        let Some(process) = system.process(pid) else {
+            return false;
+        };
+
+        match process.kill_with(signal) {
+            Some(true) => true,
+            _ => {
+                eprintln!("Failed to send {signal} to {pid}");
+                false
+            }
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for ProcessesModel {
+    type Init = ProcessesInit;
+
+    type Input = ProcessesMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        root = gtk::Popover {
+            set_css_classes: &["processes"],
+
+            connect_show[sender] => move |_| {
+                sender.input(ProcessesMsg::Show);
+            },
+
+            #[name = "list"]
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 4,
+            }
+        }
+    }
+
+    fn init(
+        init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        Self::refresh_processes(&mut init.system.borrow_mut());
+
+        let model = Self {
+            system: init.system,
+            sort: init.sort,
+            count: init.count,
+
+            terminated: HashSet::new(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            ProcessesMsg::Show => {
+                self.refresh(&widgets.list, &sender);
+
+                // The usage since the last refresh can be from long ago, refresh it again without
+                // waiting for the next update
+                let sender = sender.clone();
+                glib::timeout_add_local_once(MINIMUM_CPU_UPDATE_INTERVAL, move || {
+                    sender.input(ProcessesMsg::Refresh);
+                });
+            }
+            // Process list is only refreshed while the popover is open
+            ProcessesMsg::Refresh if root.is_visible() => {
+                self.refresh(&widgets.list, &sender);
+            }
+            ProcessesMsg::Refresh => {}
+            ProcessesMsg::Terminate(pid) => {
+                if self.signal(pid, Signal::Term) {
+                    self.terminated.insert(pid);
+                    self.refresh(&widgets.list, &sender);
+                }
+            }
+            ProcessesMsg::Kill(pid) => {
+                if self.signal(pid, Signal::Kill) {
+                    self.terminated.remove(&pid);
+                    self.refresh(&widgets.list, &sender);
+                }
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
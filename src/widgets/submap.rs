@@ -0,0 +1,100 @@
+use relm4::{
+    gtk::{self, prelude::*},
+    prelude::*,
+};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub enum SubmapMsg {
+    Submap { name: String },
+}
+
+pub struct SubmapModel {
+    /// Active submap, empty for the default one
+    submap: String,
+    /// `submap-{name}`, so that submap names can't clash with other classes
+    submap_class: String,
+    submap_map: HashMap<String, String>,
+}
+
+/// `submap-{name}`, with characters that aren't valid in CSS class names replaced by `-`
+fn submap_class(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    format!("submap-{name}")
+}
+
+impl SubmapModel {
+    fn css_classes(&self) -> Vec<&str> {
+        let mut classes = vec!["widget", "submap"];
+        if !self.submap.is_empty() {
+            classes.push(&self.submap_class);
+        }
+
+        classes
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SubmapModel {
+    type Init = HashMap<String, String>;
+
+    type Input = SubmapMsg;
+    type Output = ();
+
+    view! {
+        gtk::Label {
+            #[watch]
+            set_css_classes: &model.css_classes(),
+            #[watch]
+            set_visible: !model.submap.is_empty(),
+            #[watch]
+            set_text: model.submap_map.get(&model.submap).unwrap_or(&model.submap),
+        }
+    }
+
+    fn init(
+        submap_map: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            submap: String::new(),
+            submap_class: String::new(),
+            submap_map,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+        match message {
+            SubmapMsg::Submap { name } => {
+                self.submap_class = submap_class(&name);
+                self.submap = name;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_names() {
+        assert_eq!(submap_class("resize"), "submap-resize");
+        assert_eq!(submap_class("move_window-2"), "submap-move_window-2");
+        assert_eq!(submap_class("move window.äö"), "submap-move-window---");
+    }
+}
@@ -0,0 +1,142 @@
+use relm4::{
+    gtk::{self, prelude::*},
+    prelude::*,
+};
+use std::{fmt::Write, path::PathBuf};
+
+use crate::{
+    config::{SensorSelector, TemperatureConfig},
+    hwmon::{get_sensors, is_selected, HwmonInfo, DEFAULT_HWMON_PATH},
+};
+
+#[derive(Debug)]
+pub enum TemperatureMsg {
+    Update,
+}
+
+pub struct TemperatureModel {
+    hwmon_path: PathBuf,
+    sensors: Vec<SensorSelector>,
+    warning: Option<f32>,
+    critical: Option<f32>,
+
+    info: HwmonInfo,
+}
+
+impl TemperatureModel {
+    fn temperature(&self) -> Option<f32> {
+        self.info
+            .temperatures
+            .iter()
+            .filter(|sensor| is_selected(&self.sensors, &sensor.chip, &sensor.label))
+            .map(|sensor| sensor.temperature)
+            .reduce(f32::max)
+    }
+
+    fn is_warning(&self) -> bool {
+        self.warning
+            .zip(self.temperature())
+            .is_some_and(|(warning, temperature)| temperature >= warning)
+            && !self.is_critical()
+    }
+
+    fn is_critical(&self) -> bool {
+        self.critical
+            .zip(self.temperature())
+            .is_some_and(|(critical, temperature)| temperature >= critical)
+    }
+
+    fn format(&self) -> String {
+        match self.temperature() {
+            Some(temperature) => format!(" {temperature:.0}°C"),
+            None => " N/A".to_string(),
+        }
+    }
+
+    fn format_tooltip(&self) -> String {
+        let mut tooltip = String::new();
+
+        let temperatures = self
+            .info
+            .temperatures
+            .iter()
+            .filter(|sensor| is_selected(&self.sensors, &sensor.chip, &sensor.label));
+        for sensor in temperatures {
+            let _ = writeln!(
+                tooltip,
+                "{} {}: {:.1}°C",
+                sensor.chip, sensor.label, sensor.temperature
+            );
+        }
+
+        let fans = self
+            .info
+            .fans
+            .iter()
+            .filter(|fan| is_selected(&self.sensors, &fan.chip, &fan.label));
+        for fan in fans {
+            let _ = writeln!(tooltip, "{} {}: {} RPM", fan.chip, fan.label, fan.rpm);
+        }
+
+        tooltip.truncate(tooltip.trim_end_matches('\n').len());
+        tooltip
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for TemperatureModel {
+    type Init = TemperatureConfig;
+
+    type Input = TemperatureMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 4,
+
+            set_css_classes: &["widget", "temperature"],
+
+            #[watch]
+            set_class_active: ("warning", model.is_warning()),
+            #[watch]
+            set_class_active: ("critical", model.is_critical()),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
+
+            append: label = &gtk::Label {
+                #[watch]
+                set_text: &model.format(),
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let hwmon_path = config
+            .hwmon_path
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_HWMON_PATH));
+        let info = get_sensors(&hwmon_path);
+        let model = Self {
+            hwmon_path,
+            sensors: config.sensors.unwrap_or_default(),
+            warning: config.warning,
+            critical: config.critical,
+
+            info,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+        match message {
+            TemperatureMsg::Update => self.info = get_sensors(&self.hwmon_path),
+        }
+    }
+}
@@ -1,21 +1,163 @@
-use chrono::{DateTime, Local};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, TimeDelta, Timelike,
+};
+use chrono_tz::Tz;
 use relm4::{
-    gtk::{self, prelude::*},
+    gtk::{self, glib, prelude::*},
     prelude::*,
 };
+use std::{fmt::Write, time::Duration};
+
+use crate::{
+    config::TimeConfig,
+    ics::Event,
+    widgets::calendar::{CalendarModel, CalendarMsg, CalendarOutput},
+};
+
+const DEFAULT_FORMAT: &str = "󰅐 %H:%M";
+const DEFAULT_TOOLTIP_FORMAT: &str = "%a %H:%M";
 
 #[derive(Debug)]
 pub enum TimeMsg {
-    Update,
+    Tick { generation: u64 },
+    ToggleFormat,
+    ShowCalendar,
+    NextEvent(Option<Event>),
 }
 
 pub struct TimeModel {
+    format: String,
+    alt_format: Option<String>,
+    tooltip_format: String,
+    timezones: Vec<Tz>,
+    show_countdown: bool,
+
+    use_alt_format: bool,
     time: DateTime<Local>,
+    /// Incremented on every scheduled update to ignore ticks of outdated timers
+    generation: u64,
+
+    calendar: Option<Controller<CalendarModel>>,
+    next_event: Option<Event>,
+}
+
+/// Falls back to `default` if the format has invalid specifiers, formatting it would panic
+fn checked_format(format: Option<String>, default: &str) -> String {
+    match format {
+        Some(format) if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) => {
+            eprintln!("Invalid time format {format:?}, using {default:?}");
+            default.to_string()
+        }
+        Some(format) => format,
+        None => default.to_string(),
+    }
+}
+
+impl TimeModel {
+    fn active_format(&self) -> &str {
+        match self.alt_format {
+            Some(ref alt_format) if self.use_alt_format => alt_format,
+            _ => &self.format,
+        }
+    }
+
+    /// Checks if the format string contains specifiers that change every second
+    fn has_seconds(format: &str) -> bool {
+        let mut chars = format.chars();
+        while let Some(char) = chars.next() {
+            if char != '%' {
+                continue;
+            }
+
+            // Skip padding, width and precision modifiers
+            let specifier = chars
+                .by_ref()
+                .find(|char| !matches!(char, '-' | '_' | '0'..='9' | '^' | '#' | ':' | '.'));
+            if matches!(
+                specifier,
+                Some('S' | 'T' | 'X' | 'r' | 's' | 'c' | '+' | 'f')
+            ) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns the delay until the next second or minute boundary, depending on the format
+    fn next_update_delay(&self) -> Duration {
+        let now = Local::now();
+
+        let subsec = Duration::from_nanos(u64::from(now.nanosecond() % 1_000_000_000));
+        let delay = if Self::has_seconds(self.active_format()) {
+            Duration::from_secs(1) - subsec
+        } else {
+            Duration::from_secs(u64::from(60 - now.second())) - subsec
+        };
+
+        // Round up to avoid waking up right before the boundary
+        delay + Duration::from_millis(1)
+    }
+
+    fn schedule_update(&mut self, sender: &ComponentSender<Self>) {
+        self.generation += 1;
+
+        let generation = self.generation;
+        let sender = sender.clone();
+        glib::timeout_add_local_once(self.next_update_delay(), move || {
+            sender.input(TimeMsg::Tick { generation });
+        });
+    }
+
+    fn format_countdown(&self) -> Option<String> {
+        let event = self.next_event.as_ref()?;
+
+        let remaining = event.start - self.time;
+        if remaining <= TimeDelta::zero() || remaining > TimeDelta::days(1) {
+            return None;
+        }
+
+        // Round up so that the countdown doesn't show 0m before the event starts
+        let minutes = (remaining.num_seconds() + 59) / 60;
+        let countdown = if minutes >= 60 {
+            format!("{}h {:02}m", minutes / 60, minutes % 60)
+        } else {
+            format!("{minutes}m")
+        };
+
+        Some(format!("󰃭 {} in {countdown}", event.summary))
+    }
+
+    fn format(&self) -> String {
+        let time = self.time.format(self.active_format()).to_string();
+
+        match self.format_countdown() {
+            Some(countdown) if self.show_countdown => format!("{time} {countdown}"),
+            _ => time,
+        }
+    }
+
+    fn format_tooltip(&self) -> String {
+        let mut tooltip = self.time.format("%A, %d %B %Y").to_string();
+
+        for timezone in &self.timezones {
+            let time = self.time.with_timezone(timezone);
+            let _ = write!(
+                tooltip,
+                "\n{}: {}",
+                timezone.name(),
+                time.format(&self.tooltip_format)
+            );
+        }
+
+        tooltip
+    }
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for TimeModel {
-    type Init = ();
+    type Init = TimeConfig;
 
     type Input = TimeMsg;
     type Output = ();
@@ -23,28 +165,131 @@ impl SimpleComponent for TimeModel {
     view! {
         root = gtk::Label {
             set_css_classes: &["widget", "time"],
+
+            add_controller = gtk::GestureClick {
+                set_button: 0,
+                connect_released[sender] => move |gesture, _, _, _| {
+                    if gesture.current_button() == gtk::gdk::BUTTON_SECONDARY {
+                        sender.input(TimeMsg::ShowCalendar);
+                    } else {
+                        sender.input(TimeMsg::ToggleFormat);
+                    }
+                },
+            },
+
             #[watch]
-            set_text: &model.time.format("󰅐 %H:%M").to_string()
+            set_class_active: ("alt", model.use_alt_format),
+            #[watch]
+            set_text: &model.format(),
+            #[watch]
+            set_tooltip_text: Some(&model.format_tooltip()),
         }
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = Self { time: Local::now() };
+        let timezones = config
+            .timezones
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|timezone| match timezone.parse::<Tz>() {
+                Ok(timezone) => Some(timezone),
+                Err(err) => {
+                    eprintln!("Invalid timezone {timezone}: {err}");
+                    None
+                }
+            })
+            .collect();
+
+        let show_countdown = config
+            .calendar
+            .as_ref()
+            .and_then(|calendar| calendar.show_countdown)
+            .unwrap_or(false);
+        let calendar = config.calendar.map(|calendar| {
+            let calendar = CalendarModel::builder().launch(calendar).forward(
+                sender.input_sender(),
+                |output| match output {
+                    CalendarOutput::NextEvent(event) => TimeMsg::NextEvent(event),
+                },
+            );
+            calendar.widget().set_parent(&root);
+            calendar
+        });
+
+        let mut model = Self {
+            format: checked_format(config.format, DEFAULT_FORMAT),
+            alt_format: config
+                .alt_format
+                .map(|alt_format| checked_format(Some(alt_format), DEFAULT_FORMAT)),
+            tooltip_format: checked_format(config.tooltip_format, DEFAULT_TOOLTIP_FORMAT),
+            timezones,
+            show_countdown,
+
+            use_alt_format: false,
+            time: Local::now(),
+            generation: 0,
+
+            calendar,
+            next_event: None,
+        };
+        model.schedule_update(&sender);
 
         let widgets = view_output!();
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
-            TimeMsg::Update => {
-                self.time = Local::now();
+            TimeMsg::Tick { generation } if generation == self.generation => {}
+            TimeMsg::ToggleFormat if self.alt_format.is_some() => {
+                self.use_alt_format = !self.use_alt_format;
+            }
+            // Left clicks open the calendar too without an alternate format
+            TimeMsg::ShowCalendar | TimeMsg::ToggleFormat => {
+                if let Some(ref calendar) = self.calendar {
+                    calendar.widget().popup();
+                }
+                return;
+            }
+            TimeMsg::NextEvent(event) => {
+                self.next_event = event;
+                return;
             }
+            _ => return,
         }
+
+        self.time = Local::now();
+        self.schedule_update(&sender);
+
+        if let Some(ref calendar) = self.calendar {
+            calendar.emit(CalendarMsg::Tick);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_formats() {
+        assert_eq!(checked_format(None, DEFAULT_FORMAT), DEFAULT_FORMAT);
+        assert_eq!(
+            checked_format(Some("%H:%M:%S".to_string()), DEFAULT_FORMAT),
+            "%H:%M:%S"
+        );
+        assert_eq!(
+            checked_format(Some("%H %Q".to_string()), DEFAULT_FORMAT),
+            DEFAULT_FORMAT
+        );
+        assert_eq!(
+            checked_format(Some("%H %".to_string()), DEFAULT_TOOLTIP_FORMAT),
+            DEFAULT_TOOLTIP_FORMAT
+        );
     }
 }
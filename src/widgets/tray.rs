@@ -0,0 +1,609 @@
+use relm4::{
+    gtk::{
+        self,
+        gdk::{self, Display},
+        gio::{self, BusType, DBusConnection},
+        glib::{self, MainContext},
+        prelude::*,
+    },
+    prelude::*,
+};
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    config::TrayConfig,
+    status_notifier::{
+        self, HostEvent, Item, ItemProperties, ItemStatus, Menu, MenuItem, Pixmap, ToggleType,
+    },
+};
+
+const DEFAULT_ICON_SIZE: i32 = 16;
+
+#[derive(Debug)]
+pub enum TrayMsg {
+    Connected(DBusConnection),
+    Host(HostEvent),
+    ItemReady(Item),
+    Changed(String),
+    Properties {
+        id: String,
+        properties: ItemProperties,
+    },
+    Activate(String),
+    SecondaryActivate(String),
+    Scroll {
+        id: String,
+        delta: i32,
+    },
+    ShowMenu(String),
+    MenuLayout {
+        id: String,
+        menu: Menu,
+        layout: MenuItem,
+    },
+    MenuClicked {
+        id: String,
+        menu_id: i32,
+    },
+    MenuToggles {
+        id: String,
+        toggles: Vec<(i32, bool)>,
+    },
+}
+
+/// Actions of the shown dbusmenu layout
+struct MenuActions {
+    group: gio::SimpleActionGroup,
+    /// Name of the action shared by the radio items of each group, by item id
+    radio_groups: HashMap<i32, String>,
+}
+
+impl MenuActions {
+    fn new() -> Self {
+        Self {
+            group: gio::SimpleActionGroup::new(),
+            radio_groups: HashMap::new(),
+        }
+    }
+
+    /// Updates the state of a checkmark or radio item after the app changed it
+    fn set_toggled(&self, menu_id: i32, toggled: bool) {
+        if let Some(radio_group) = self.radio_groups.get(&menu_id) {
+            let Some(action) = self.group.lookup_action(radio_group) else {
+                return;
+            };
+
+            // Radio groups have the id of the selected item as their state
+            let target = menu_id.to_string().to_variant();
+            if toggled {
+                action.change_state(&target);
+            } else if action.state().as_ref() == Some(&target) {
+                action.change_state(&"".to_variant());
+            }
+        } else if let Some(action) = self.group.lookup_action(&format!("item-{menu_id}")) {
+            if action.state().is_some() {
+                action.change_state(&toggled.to_variant());
+            }
+        }
+    }
+}
+
+struct TrayItem {
+    item: Item,
+    properties: ItemProperties,
+    image: gtk::Image,
+    popover: gtk::PopoverMenu,
+    menu: Option<Menu>,
+    menu_actions: Option<MenuActions>,
+}
+
+pub struct TrayModel {
+    icon_size: i32,
+    connection: Option<DBusConnection>,
+    /// Items in the order they were registered
+    items: Vec<TrayItem>,
+}
+
+/// The smallest pixmap that's at least as large as the icon, otherwise the largest one
+fn best_pixmap(pixmaps: &[Pixmap], size: i32) -> Option<&Pixmap> {
+    pixmaps
+        .iter()
+        .filter(|pixmap| pixmap.width >= size)
+        .min_by_key(|pixmap| pixmap.width)
+        .or_else(|| pixmaps.iter().max_by_key(|pixmap| pixmap.width))
+}
+
+fn update_icon(image: &gtk::Image, properties: &ItemProperties) {
+    let needs_attention = properties.status == ItemStatus::NeedsAttention
+        && (!properties.attention_icon_name.is_empty()
+            || !properties.attention_icon_pixmaps.is_empty());
+    let (icon_name, pixmaps) = if needs_attention {
+        (
+            &properties.attention_icon_name,
+            &properties.attention_icon_pixmaps,
+        )
+    } else {
+        (&properties.icon_name, &properties.icon_pixmaps)
+    };
+
+    let theme = gtk::IconTheme::for_display(&Display::default().unwrap());
+    if !properties.icon_theme_path.is_empty()
+        && !theme
+            .search_path()
+            .iter()
+            .any(|path| *path == Path::new(&properties.icon_theme_path))
+    {
+        theme.add_search_path(&properties.icon_theme_path);
+    }
+
+    if Path::new(icon_name).is_absolute() {
+        image.set_from_file(Some(icon_name));
+    } else if !icon_name.is_empty() && theme.has_icon(icon_name) {
+        image.set_icon_name(Some(icon_name));
+    } else if let Some(pixmap) = best_pixmap(pixmaps, image.pixel_size()) {
+        let texture = gdk::MemoryTexture::new(
+            pixmap.width,
+            pixmap.height,
+            gdk::MemoryFormat::A8r8g8b8,
+            &glib::Bytes::from(&pixmap.data),
+            pixmap.width as usize * 4,
+        );
+        image.set_paintable(Some(&texture));
+    } else {
+        image.set_icon_name(Some("image-missing"));
+    }
+
+    image.set_visible(properties.status != ItemStatus::Passive);
+    image.set_class_active("needs-attention", needs_attention);
+
+    let tooltip = if properties.tooltip.is_empty() {
+        &properties.title
+    } else {
+        &properties.tooltip
+    };
+    image.set_tooltip_text(Some(tooltip.as_str()).filter(|tooltip| !tooltip.is_empty()));
+}
+
+/// Sends a click for the item id returned by `f`, which also updates the state of the action
+fn connect_click(
+    action: &gio::SimpleAction,
+    sender: &ComponentSender<TrayModel>,
+    id: &str,
+    f: impl Fn(&gio::SimpleAction, Option<&glib::Variant>) -> Option<i32> + 'static,
+) {
+    action.connect_activate({
+        let sender = sender.clone();
+        let id = id.to_string();
+        move |action, parameter| {
+            if let Some(menu_id) = f(action, parameter) {
+                sender.input(TrayMsg::MenuClicked {
+                    id: id.clone(),
+                    menu_id,
+                });
+            }
+        }
+    });
+}
+
+fn build_menu(
+    items: &[MenuItem],
+    actions: &mut MenuActions,
+    sender: &ComponentSender<TrayModel>,
+    id: &str,
+) -> gio::Menu {
+    let menu = gio::Menu::new();
+    let mut section = gio::Menu::new();
+    // Consecutive radio items form a group
+    let mut radio_group: Option<gio::SimpleAction> = None;
+
+    for item in items.iter().filter(|item| item.visible) {
+        if !matches!(item.toggle, Some((ToggleType::Radio, _))) || !item.children.is_empty() {
+            radio_group = None;
+        }
+
+        // Separators split the menu into sections
+        if item.separator {
+            if section.n_items() != 0 {
+                menu.append_section(None, &section);
+                section = gio::Menu::new();
+            }
+            continue;
+        }
+
+        if !item.children.is_empty() {
+            let submenu = build_menu(&item.children, actions, sender, id);
+            section.append_submenu(Some(&item.label), &submenu);
+            continue;
+        }
+
+        if let Some((ToggleType::Radio, toggled)) = item.toggle {
+            // Items of a group share one action with the id of the selected item as state,
+            // so a group is enabled if any of its items are
+            let action = radio_group.get_or_insert_with(|| {
+                let action = gio::SimpleAction::new_stateful(
+                    &format!("radio-{}", item.id),
+                    Some(glib::VariantTy::STRING),
+                    &"".to_variant(),
+                );
+                action.set_enabled(false);
+                connect_click(&action, sender, id, |action, parameter| {
+                    let parameter = parameter?;
+                    action.change_state(parameter);
+                    parameter.str()?.parse().ok()
+                });
+                actions.group.add_action(&action);
+                action
+            });
+            action.set_enabled(action.is_enabled() || item.enabled);
+            if toggled {
+                action.change_state(&item.id.to_string().to_variant());
+            }
+
+            let action_name = action.name();
+            actions
+                .radio_groups
+                .insert(item.id, action_name.to_string());
+            section.append(
+                Some(&item.label),
+                Some(&format!("dbusmenu.{action_name}::{}", item.id)),
+            );
+            continue;
+        }
+
+        let action_name = format!("item-{}", item.id);
+        let menu_id = item.id;
+        let action = match item.toggle {
+            Some((_, toggled)) => {
+                let action =
+                    gio::SimpleAction::new_stateful(&action_name, None, &toggled.to_variant());
+                connect_click(&action, sender, id, move |action, _| {
+                    let toggled = action.state()?.get::<bool>()?;
+                    action.change_state(&(!toggled).to_variant());
+                    Some(menu_id)
+                });
+                action
+            }
+            None => {
+                let action = gio::SimpleAction::new(&action_name, None);
+                connect_click(&action, sender, id, move |_, _| Some(menu_id));
+                action
+            }
+        };
+        action.set_enabled(item.enabled);
+        actions.group.add_action(&action);
+
+        // Labels use underscores for mnemonics like GMenu does
+        section.append(Some(&item.label), Some(&format!("dbusmenu.{action_name}")));
+    }
+
+    if section.n_items() != 0 {
+        menu.append_section(None, &section);
+    }
+
+    menu
+}
+
+impl TrayModel {
+    fn find(&self, id: &str) -> Option<&TrayItem> {
+        self.items.iter().find(|tray_item| tray_item.item.id == id)
+    }
+
+    fn spawn_item_call<F, Fut>(&self, id: &str, f: F)
+    where
+        F: FnOnce(Item) -> Fut + 'static,
+        Fut: std::future::Future<Output = Result<(), glib::Error>> + 'static,
+    {
+        let Some(tray_item) = self.find(id) else {
+            return;
+        };
+
+        let item = tray_item.item.clone();
+        MainContext::default().spawn_local(async move {
+            let id = item.id.clone();
+            if let Err(err) = f(item).await {
+                eprintln!("Tray item {id}: {err}");
+            }
+        });
+    }
+
+    fn add_item(&mut self, root: &gtk::Box, item: Item, sender: &ComponentSender<Self>) {
+        if self.find(&item.id).is_some() {
+            return;
+        }
+
+        let image = gtk::Image::new();
+        image.set_pixel_size(self.icon_size);
+        image.set_css_classes(&["item"]);
+        // Hidden until the properties arrive
+        image.set_visible(false);
+
+        let click = gtk::GestureClick::new();
+        click.set_button(0);
+        click.connect_released({
+            let sender = sender.clone();
+            let id = item.id.clone();
+            move |gesture, _, _, _| match gesture.current_button() {
+                gdk::BUTTON_PRIMARY => sender.input(TrayMsg::Activate(id.clone())),
+                gdk::BUTTON_MIDDLE => sender.input(TrayMsg::SecondaryActivate(id.clone())),
+                gdk::BUTTON_SECONDARY => sender.input(TrayMsg::ShowMenu(id.clone())),
+                _ => {}
+            }
+        });
+        image.add_controller(click);
+
+        let scroll = gtk::EventControllerScroll::new(
+            gtk::EventControllerScrollFlags::VERTICAL | gtk::EventControllerScrollFlags::DISCRETE,
+        );
+        scroll.connect_scroll({
+            let sender = sender.clone();
+            let id = item.id.clone();
+            move |_, _, dy| {
+                sender.input(TrayMsg::Scroll {
+                    id: id.clone(),
+                    delta: dy as i32,
+                });
+                glib::Propagation::Stop
+            }
+        });
+        image.add_controller(scroll);
+
+        let popover = gtk::PopoverMenu::from_model(None::<&gio::MenuModel>);
+        popover.set_parent(&image);
+
+        item.connect_changed({
+            let sender = sender.clone();
+            let id = item.id.clone();
+            move || sender.input(TrayMsg::Changed(id.clone()))
+        });
+        sender.input(TrayMsg::Changed(item.id.clone()));
+
+        root.append(&image);
+        self.items.push(TrayItem {
+            item,
+            properties: ItemProperties::default(),
+            image,
+            popover,
+            menu: None,
+            menu_actions: None,
+        });
+    }
+
+    fn remove_item(&mut self, root: &gtk::Box, id: &str) {
+        self.items.retain(|tray_item| {
+            if tray_item.item.id != id {
+                return true;
+            }
+
+            tray_item.popover.unparent();
+            root.remove(&tray_item.image);
+            false
+        });
+    }
+
+    fn show_menu(&self, id: &str, sender: &ComponentSender<Self>) {
+        let Some(tray_item) = self.find(id) else {
+            return;
+        };
+
+        let Some(menu_path) = tray_item.properties.menu.clone() else {
+            // Items without a dbusmenu draw their own
+            self.spawn_item_call(id, |item| async move { item.context_menu().await });
+            return;
+        };
+
+        let Some(connection) = self.connection.clone() else {
+            return;
+        };
+
+        let sender = sender.clone();
+        let id = id.to_string();
+        let bus_name = tray_item.item.bus_name.clone();
+        MainContext::default().spawn_local(async move {
+            let menu = match Menu::new(&connection, &bus_name, &menu_path).await {
+                Ok(menu) => menu,
+                Err(err) => {
+                    eprintln!("Failed to create menu proxy for {id}: {err}");
+                    return;
+                }
+            };
+
+            match menu.layout().await {
+                Ok(Some(layout)) => sender.input(TrayMsg::MenuLayout { id, menu, layout }),
+                Ok(None) => eprintln!("Invalid menu layout for {id}"),
+                Err(err) => eprintln!("Failed to get menu layout for {id}: {err}"),
+            }
+        });
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for TrayModel {
+    type Init = TrayConfig;
+
+    type Input = TrayMsg;
+    type Output = ();
+
+    type CommandOutput = ();
+
+    view! {
+        root = gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 4,
+
+            set_css_classes: &["widget", "tray"],
+
+            #[watch]
+            set_visible: model
+                .items
+                .iter()
+                .any(|tray_item| tray_item.properties.status != ItemStatus::Passive),
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            icon_size: config.icon_size.unwrap_or(DEFAULT_ICON_SIZE),
+            connection: None,
+            items: Vec::new(),
+        };
+
+        MainContext::default().spawn_local({
+            let sender = sender.clone();
+            async move {
+                let connection = match gio::bus_get_future(BusType::Session).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        eprintln!("Failed to connect to the session bus: {err}");
+                        return;
+                    }
+                };
+                sender.input(TrayMsg::Connected(connection.clone()));
+
+                if let Err(err) = status_notifier::start_watcher(&connection) {
+                    eprintln!("Failed to start the tray watcher: {err}");
+                }
+
+                status_notifier::start_host(&connection, move |event| {
+                    sender.input(TrayMsg::Host(event));
+                });
+            }
+        });
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            TrayMsg::Connected(connection) => self.connection = Some(connection),
+            TrayMsg::Host(HostEvent::ItemRegistered(id)) => {
+                let Some(connection) = self.connection.clone() else {
+                    return;
+                };
+
+                let sender = sender.clone();
+                MainContext::default().spawn_local(async move {
+                    match Item::new(&connection, id.clone()).await {
+                        Ok(item) => sender.input(TrayMsg::ItemReady(item)),
+                        Err(err) => eprintln!("Failed to create proxy for tray item {id}: {err}"),
+                    }
+                });
+            }
+            TrayMsg::Host(HostEvent::ItemUnregistered(id)) => self.remove_item(root, &id),
+            TrayMsg::Host(HostEvent::WatcherVanished) => {
+                let ids = self
+                    .items
+                    .iter()
+                    .map(|tray_item| tray_item.item.id.clone())
+                    .collect::<Vec<_>>();
+                for id in ids {
+                    self.remove_item(root, &id);
+                }
+            }
+            TrayMsg::ItemReady(item) => self.add_item(root, item, &sender),
+            TrayMsg::Changed(id) => {
+                let sender = sender.clone();
+                self.spawn_item_call(&id, |item| async move {
+                    let properties = item.properties().await?;
+                    sender.input(TrayMsg::Properties {
+                        id: item.id,
+                        properties,
+                    });
+                    Ok(())
+                });
+            }
+            TrayMsg::Properties { id, properties } => {
+                if let Some(tray_item) = self
+                    .items
+                    .iter_mut()
+                    .find(|tray_item| tray_item.item.id == id)
+                {
+                    update_icon(&tray_item.image, &properties);
+                    tray_item.properties = properties;
+                }
+            }
+            TrayMsg::Activate(id) => {
+                if self
+                    .find(&id)
+                    .is_some_and(|tray_item| tray_item.properties.item_is_menu)
+                {
+                    self.show_menu(&id, &sender);
+                } else {
+                    self.spawn_item_call(&id, |item| async move { item.activate().await });
+                }
+            }
+            TrayMsg::SecondaryActivate(id) => {
+                self.spawn_item_call(&id, |item| async move { item.secondary_activate().await });
+            }
+            TrayMsg::Scroll { id, delta } => {
+                self.spawn_item_call(&id, move |item| async move { item.scroll(delta).await });
+            }
+            TrayMsg::ShowMenu(id) => self.show_menu(&id, &sender),
+            TrayMsg::MenuLayout { id, menu, layout } => {
+                if let Some(tray_item) = self
+                    .items
+                    .iter_mut()
+                    .find(|tray_item| tray_item.item.id == id)
+                {
+                    let mut actions = MenuActions::new();
+                    let menu_model = build_menu(&layout.children, &mut actions, &sender, &id);
+
+                    menu.connect_toggles_changed({
+                        let sender = sender.clone();
+                        let id = id.clone();
+                        move |toggles| {
+                            sender.input(TrayMsg::MenuToggles {
+                                id: id.clone(),
+                                toggles,
+                            });
+                        }
+                    });
+
+                    tray_item
+                        .popover
+                        .insert_action_group("dbusmenu", Some(&actions.group));
+                    tray_item.popover.set_menu_model(Some(&menu_model));
+                    tray_item.popover.popup();
+                    tray_item.menu = Some(menu);
+                    tray_item.menu_actions = Some(actions);
+                }
+            }
+            TrayMsg::MenuClicked { id, menu_id } => {
+                let Some(menu) = self.find(&id).and_then(|tray_item| tray_item.menu.clone()) else {
+                    return;
+                };
+
+                MainContext::default().spawn_local(async move {
+                    if let Err(err) = menu.click(menu_id).await {
+                        eprintln!("Failed to click menu item of {id}: {err}");
+                    }
+                });
+            }
+            TrayMsg::MenuToggles { id, toggles } => {
+                let Some(actions) = self
+                    .find(&id)
+                    .and_then(|tray_item| tray_item.menu_actions.as_ref())
+                else {
+                    return;
+                };
+
+                for (menu_id, toggled) in toggles {
+                    actions.set_toggled(menu_id, toggled);
+                }
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
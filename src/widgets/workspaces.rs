@@ -1,70 +1,426 @@
 use relm4::{
-    gtk::{self, glib::WeakRef, prelude::*},
+    gtk::{self, prelude::*},
     prelude::*,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::hyprland::Workspace;
+use crate::{
+    config::{PersistentWorkspace, WorkspacesConfig},
+    hyprland::{Client, Monitor, Workspace},
+};
+
+const SPECIAL_PREFIX: &str = "special:";
 
 #[derive(Debug)]
 pub enum WorkspacesMsg {
-    Activate { id: usize },
-    Create { id: usize, name: String },
-    Destroy { id: usize },
+    Activate {
+        id: i64,
+    },
+    Create {
+        id: i64,
+        name: String,
+    },
+    Destroy {
+        id: i64,
+    },
+    ActivateSpecial {
+        name: String,
+        monitor: String,
+    },
+    FocusMonitor {
+        monitor: String,
+        workspace_name: String,
+    },
+    Move {
+        id: i64,
+        monitor: String,
+    },
+    OpenWindow {
+        address: String,
+        workspace_name: String,
+        class: String,
+    },
+    CloseWindow {
+        address: String,
+    },
+    Urgent {
+        address: String,
+    },
+    MoveWindow {
+        address: String,
+        workspace_id: i64,
+    },
+}
+
+pub struct WorkspacesInit {
+    pub workspaces: Vec<Workspace>,
+    pub clients: Vec<Client>,
+    pub monitors: Vec<Monitor>,
+    /// Only show workspaces of this monitor
+    pub monitor: Option<String>,
+    pub config: WorkspacesConfig,
 }
 
-type WorkspaceMap = HashMap<usize, gtk::Label>;
+struct WorkspaceEntry {
+    name: String,
+    /// `None` for persistent workspaces that don't exist and aren't bound to a monitor
+    monitor: Option<String>,
+    label: gtk::Label,
+}
+
+impl WorkspaceEntry {
+    fn display_name(&self) -> &str {
+        self.name.strip_prefix(SPECIAL_PREFIX).unwrap_or(&self.name)
+    }
+}
+
+struct Window {
+    class: String,
+    workspace: i64,
+}
+
+type WorkspaceMap = HashMap<i64, WorkspaceEntry>;
 pub struct WorkspacesModel {
-    old_workspace: Option<WeakRef<gtk::Label>>,
+    monitor: Option<String>,
+    show_all_monitors: bool,
+    window_icons: Option<HashMap<String, String>>,
+    default_window_icon: Option<String>,
+    persistent_workspaces: HashMap<i64, PersistentWorkspace>,
+
     workspaces: WorkspaceMap,
+
+    special_box: gtk::Box,
+    special_workspaces: WorkspaceMap,
+
+    focused_monitor: String,
+    active_workspace: Option<i64>,
+    /// Workspace shown on each monitor
+    visible_workspaces: HashMap<String, i64>,
+    /// Name of the special workspace opened on each monitor
+    open_special_workspaces: HashMap<String, String>,
+    /// Windows by address, without the `0x` prefix
+    windows: HashMap<String, Window>,
+    /// Workspaces with windows that requested attention since they were last focused
+    urgent_workspaces: HashSet<i64>,
 }
 
 impl WorkspacesModel {
-    fn add_workspace(&mut self, root: &gtk::Box, id: usize, name: &str) {
-        let workspace = gtk::Label::new(Some(name));
-        workspace.set_css_classes(&["workspace"]);
+    fn insert_sorted(container: &gtk::Box, workspaces: &WorkspaceMap, id: i64, label: &gtk::Label) {
+        let insert_after = workspaces
+            .iter()
+            .filter(|(other_id, _)| **other_id < id)
+            .max_by_key(|(other_id, _)| **other_id);
+
+        if let Some((_, insert_after)) = insert_after {
+            container.insert_child_after(label, Some(&insert_after.label));
+        } else {
+            container.prepend(label);
+        }
+    }
 
-        let mut sorted_workspaces = self.workspaces.iter().collect::<Vec<_>>();
-        sorted_workspaces.sort_unstable_by_key(|(id, _)| **id);
+    fn add_workspace(&mut self, root: &gtk::Box, id: i64, name: &str, monitor: Option<&str>) {
+        // Persistent workspaces already have a label
+        if let Some(workspace) = self
+            .workspaces
+            .get_mut(&id)
+            .or_else(|| self.special_workspaces.get_mut(&id))
+        {
+            workspace.name = name.to_string();
+            workspace.monitor = monitor.map(str::to_owned);
+            return;
+        }
+
+        let entry = |label: gtk::Label| WorkspaceEntry {
+            name: name.to_string(),
+            monitor: monitor.map(str::to_owned),
+            label,
+        };
+
+        if let Some(special_name) = name.strip_prefix(SPECIAL_PREFIX) {
+            let label = gtk::Label::new(Some(special_name));
+            label.set_css_classes(&["workspace", "special"]);
+
+            Self::insert_sorted(&self.special_box, &self.special_workspaces, id, &label);
+            self.special_workspaces.insert(id, entry(label));
+            return;
+        }
 
-        let insert_after = sorted_workspaces
+        let label = gtk::Label::new(Some(name));
+        label.set_css_classes(&["workspace"]);
+
+        Self::insert_sorted(root, &self.workspaces, id, &label);
+        self.workspaces.insert(id, entry(label));
+    }
+
+    fn add_persistent_workspace(&mut self, root: &gtk::Box, id: i64) {
+        let Some(persistent) = self.persistent_workspaces.get(&id) else {
+            return;
+        };
+
+        let name = persistent.name.clone().unwrap_or_else(|| id.to_string());
+        let monitor = persistent.monitor.clone();
+        self.add_workspace(root, id, &name, monitor.as_deref());
+    }
+
+    fn remove_workspace(&mut self, root: &gtk::Box, id: i64) {
+        self.urgent_workspaces.remove(&id);
+
+        // Persistent workspaces are kept, but lose their monitor
+        if self.persistent_workspaces.contains_key(&id) {
+            self.add_persistent_workspace(root, id);
+            return;
+        }
+
+        if let Some(special_workspace) = self.special_workspaces.remove(&id) {
+            self.special_box.remove(&special_workspace.label);
+            return;
+        }
+
+        // Workspaces created before the initial fetch finished or when it failed are unknown
+        let Some(workspace) = self.workspaces.remove(&id) else {
+            return;
+        };
+        root.remove(&workspace.label);
+    }
+
+    fn activate_workspace(&mut self, id: i64) {
+        // Workspaces are always activated on the focused monitor
+        if let Some(workspace) = self.workspaces.get_mut(&id) {
+            workspace.monitor = Some(self.focused_monitor.clone());
+        }
+
+        self.active_workspace = Some(id);
+        self.urgent_workspaces.remove(&id);
+        self.visible_workspaces
+            .insert(self.focused_monitor.clone(), id);
+    }
+
+    fn focus_monitor(&mut self, monitor: String, workspace_name: &str) {
+        self.focused_monitor = monitor;
+
+        let id = self
+            .workspaces
             .iter()
-            .take_while(|(other_id, _)| **other_id < id)
-            .last();
+            .find(|(_, workspace)| workspace.name == workspace_name)
+            .map(|(id, _)| *id);
+        if let Some(id) = id {
+            self.activate_workspace(id);
+        }
+    }
 
-        if let Some((_, insert_after)) = insert_after {
-            root.insert_child_after(&workspace, Some(*insert_after));
-        } else if sorted_workspaces.first().is_some_and(|other| id > *other.0) {
-            root.append(&workspace);
+    fn move_workspace(&mut self, id: i64, monitor: String) {
+        if let Some(workspace) = self
+            .workspaces
+            .get_mut(&id)
+            .or_else(|| self.special_workspaces.get_mut(&id))
+        {
+            workspace.monitor = Some(monitor);
+        }
+
+        // The monitor it was moved from shows another workspace now
+        self.visible_workspaces
+            .retain(|_, visible_id| *visible_id != id);
+    }
+
+    /// Whether the workspace is shown on any monitor, not only the focused one
+    fn is_visible(&self, id: i64) -> bool {
+        self.visible_workspaces
+            .values()
+            .any(|visible| *visible == id)
+    }
+
+    fn find_workspace(&self, name: &str) -> Option<i64> {
+        self.workspaces
+            .iter()
+            .chain(&self.special_workspaces)
+            .find(|(_, workspace)| workspace.name == name)
+            .map(|(id, _)| *id)
+    }
+
+    fn window_icon(&self, class: &str) -> Option<&str> {
+        let window_icons = self.window_icons.as_ref()?;
+        window_icons
+            .get(class)
+            .or(self.default_window_icon.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Updates the text and the `empty`/`occupied` classes from the windows on the workspace
+    fn update_windows(&self, id: i64, workspace: &WorkspaceEntry) {
+        let classes = self
+            .windows
+            .values()
+            .filter(|window| window.workspace == id)
+            .map(|window| window.class.as_str())
+            .collect::<Vec<_>>();
+
+        let icons = classes
+            .iter()
+            .filter_map(|class| self.window_icon(class))
+            .collect::<Vec<_>>();
+
+        let label = &workspace.label;
+        if icons.is_empty() {
+            label.set_text(self.display_name(id, workspace));
         } else {
-            root.prepend(&workspace);
+            label.set_text(&format!(
+                "{} {}",
+                self.display_name(id, workspace),
+                icons.join(" ")
+            ));
+        }
+
+        label.set_class_active("urgent", self.urgent_workspaces.contains(&id));
+        label.set_class_active("empty", classes.is_empty());
+        label.set_class_active("occupied", !classes.is_empty());
+        label.set_tooltip_text(Some(&match classes.len() {
+            1 => "1 window".to_string(),
+            count => format!("{count} windows"),
+        }));
+    }
+
+    fn is_on_monitor(&self, workspace: &WorkspaceEntry) -> bool {
+        match (&self.monitor, &workspace.monitor) {
+            (Some(monitor), Some(workspace_monitor)) => monitor == workspace_monitor,
+            _ => true,
         }
+    }
 
-        self.workspaces.insert(id, workspace);
+    fn display_name<'a>(&'a self, id: i64, workspace: &'a WorkspaceEntry) -> &'a str {
+        self.persistent_workspaces
+            .get(&id)
+            .and_then(|persistent| persistent.icon.as_ref().or(persistent.name.as_ref()))
+            .map_or_else(|| workspace.display_name(), String::as_str)
     }
 
-    fn remove_workspace(&mut self, root: &gtk::Box, id: usize) {
-        let workspace = self.workspaces.remove(&id).expect("Workspace not found");
-        root.remove(&workspace);
+    fn update_classes(&self) {
+        for (id, workspace) in &self.workspaces {
+            let on_monitor = self.is_on_monitor(workspace);
+            let label = &workspace.label;
+
+            self.update_windows(*id, workspace);
+            label.set_class_active("active", self.active_workspace == Some(*id));
+            label.set_class_active("visible", self.is_visible(*id));
+            label.set_class_active("other-monitor", !on_monitor);
+            label.set_visible(on_monitor || self.show_all_monitors);
+        }
+
+        for (id, workspace) in &self.special_workspaces {
+            let on_monitor = self.is_on_monitor(workspace);
+            let label = &workspace.label;
+
+            self.update_windows(*id, workspace);
+            label.set_class_active(
+                "open",
+                self.open_special_workspaces
+                    .values()
+                    .any(|name| *name == workspace.name),
+            );
+            label.set_class_active("other-monitor", !on_monitor);
+            label.set_visible(on_monitor || self.show_all_monitors);
+        }
+
+        self.special_box.set_visible(
+            self.special_workspaces
+                .values()
+                .any(|workspace| workspace.label.is_visible()),
+        );
     }
 
-    fn activate_workspace(&mut self, id: usize) {
-        if let Some(ref old_workspace) = self.old_workspace {
-            if let Some(old_workspace) = old_workspace.upgrade() {
-                old_workspace.remove_css_class("active");
+    fn new(init: WorkspacesInit, root: &gtk::Box) -> Self {
+        let special_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        special_box.set_css_classes(&["special-workspaces"]);
+        root.append(&special_box);
+
+        let mut model = Self {
+            monitor: init.monitor,
+            show_all_monitors: init.config.show_all_monitors.unwrap_or(false),
+            window_icons: init.config.window_icons,
+            default_window_icon: init.config.default_window_icon,
+            persistent_workspaces: init
+                .config
+                .persistent
+                .unwrap_or_default()
+                .into_iter()
+                .map(|persistent| (persistent.id, persistent))
+                .collect(),
+
+            workspaces: HashMap::new(),
+
+            special_box,
+            special_workspaces: HashMap::new(),
+
+            focused_monitor: String::new(),
+            active_workspace: None,
+            visible_workspaces: HashMap::new(),
+            open_special_workspaces: HashMap::new(),
+            windows: HashMap::new(),
+            urgent_workspaces: HashSet::new(),
+        };
+
+        for workspace in init.workspaces {
+            model.add_workspace(
+                root,
+                workspace.id,
+                &workspace.name,
+                Some(&workspace.monitor),
+            );
+        }
+
+        let persistent_ids = model
+            .persistent_workspaces
+            .keys()
+            .copied()
+            .filter(|id| {
+                !model.workspaces.contains_key(id) && !model.special_workspaces.contains_key(id)
+            })
+            .collect::<Vec<_>>();
+        for id in persistent_ids {
+            model.add_persistent_workspace(root, id);
+        }
+
+        for client in init.clients.into_iter().filter(|client| client.mapped) {
+            // Events refer to windows by their address without the prefix
+            let address = client
+                .address
+                .strip_prefix("0x")
+                .unwrap_or(&client.address)
+                .to_string();
+            model.windows.insert(
+                address,
+                Window {
+                    class: client.class,
+                    workspace: client.workspace.id,
+                },
+            );
+        }
+
+        for monitor in init.monitors {
+            if monitor.focused {
+                model.focused_monitor.clone_from(&monitor.name);
+                model.active_workspace = Some(monitor.active_workspace.id);
+            }
+
+            if !monitor.special_workspace.name.is_empty() {
+                model
+                    .open_special_workspaces
+                    .insert(monitor.name.clone(), monitor.special_workspace.name);
             }
+
+            model
+                .visible_workspaces
+                .insert(monitor.name, monitor.active_workspace.id);
         }
 
-        let workspace = self.workspaces.get_mut(&id).expect("Workspace not found");
-        workspace.add_css_class("active");
+        model.update_classes();
 
-        self.old_workspace = Some(workspace.downgrade());
+        model
     }
 }
 
 #[relm4::component(pub)]
 impl Component for WorkspacesModel {
-    type Init = Vec<Workspace>;
+    type Init = WorkspacesInit;
 
     type Input = WorkspacesMsg;
     type Output = ();
@@ -81,18 +437,11 @@ impl Component for WorkspacesModel {
     }
 
     fn init(
-        workspaces: Self::Init,
+        init: Self::Init,
         root: Self::Root,
         _sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let mut model = Self {
-            old_workspace: None,
-            workspaces: HashMap::new(),
-        };
-
-        for workspace in workspaces {
-            model.add_workspace(&root, workspace.id, &workspace.name);
-        }
+        let model = Self::new(init, &root);
 
         let widgets = view_output!();
 
@@ -108,10 +457,55 @@ impl Component for WorkspacesModel {
     ) {
         match message {
             WorkspacesMsg::Activate { id, .. } => self.activate_workspace(id),
-            WorkspacesMsg::Create { id, name } => self.add_workspace(root, id, &name),
+            WorkspacesMsg::Create { id, name } => {
+                // New workspaces are created on the focused monitor
+                let monitor = self.focused_monitor.clone();
+                self.add_workspace(root, id, &name, Some(&monitor));
+            }
             WorkspacesMsg::Destroy { id, .. } => self.remove_workspace(root, id),
+            WorkspacesMsg::ActivateSpecial { name, monitor } => {
+                if name.is_empty() {
+                    self.open_special_workspaces.remove(&monitor);
+                } else {
+                    self.open_special_workspaces.insert(monitor, name);
+                }
+            }
+            WorkspacesMsg::FocusMonitor {
+                monitor,
+                workspace_name,
+            } => self.focus_monitor(monitor, &workspace_name),
+            WorkspacesMsg::Move { id, monitor } => self.move_workspace(id, monitor),
+            WorkspacesMsg::OpenWindow {
+                address,
+                workspace_name,
+                class,
+            } => {
+                if let Some(workspace) = self.find_workspace(&workspace_name) {
+                    self.windows.insert(address, Window { class, workspace });
+                }
+            }
+            WorkspacesMsg::CloseWindow { address } => {
+                self.windows.remove(&address);
+            }
+            WorkspacesMsg::Urgent { address } => {
+                let workspace = self.windows.get(&address).map(|window| window.workspace);
+                if let Some(workspace) = workspace {
+                    if !self.is_visible(workspace) {
+                        self.urgent_workspaces.insert(workspace);
+                    }
+                }
+            }
+            WorkspacesMsg::MoveWindow {
+                address,
+                workspace_id,
+            } => {
+                if let Some(window) = self.windows.get_mut(&address) {
+                    window.workspace = workspace_id;
+                }
+            }
         }
 
+        self.update_classes();
         self.update_view(widgets, sender);
     }
 }
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use crate::sysfs::read_to_string_buf;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZramStats {
+    /// Uncompressed size of the data stored in zram
+    pub original: u64,
+    /// Compressed size of the data stored in zram
+    pub compressed: u64,
+    /// Memory used by zram, including the allocator overhead
+    pub used: u64,
+}
+
+// https://www.kernel.org/doc/html/latest/admin-guide/blockdev/zram.html#stats
+pub fn get_zram_stats() -> ZramStats {
+    let mut stats = ZramStats::default();
+
+    let Ok(entries) = Path::new("/sys/block").read_dir() else {
+        return stats;
+    };
+
+    let mut buffer = String::with_capacity(128);
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with("zram") {
+            continue;
+        }
+
+        let Ok(mm_stat) = read_to_string_buf(entry.path().join("mm_stat"), &mut buffer) else {
+            continue;
+        };
+
+        let mut fields = mm_stat
+            .split_ascii_whitespace()
+            .map(|field| field.parse::<u64>().unwrap_or(0));
+
+        stats.original += fields.next().unwrap_or(0);
+        stats.compressed += fields.next().unwrap_or(0);
+        stats.used += fields.next().unwrap_or(0);
+    }
+
+    stats
+}